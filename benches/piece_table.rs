@@ -0,0 +1,170 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use text_editor::window::piece_table::PieceTable;
+use text_editor::window::segment::Segment;
+
+const LINES: usize = 100_000;
+const VIEWPORT: usize = 50;
+
+/// previous implementation, pieces in a Vec and lines found by scanning from the first one
+mod vec_table {
+    use text_editor::window::segment::Segment;
+
+    pub struct Piece {
+        pub offset: usize,
+        pub length: usize,
+    }
+
+    pub struct PieceTable {
+        pub original: String,
+        pub pieces: Vec<Piece>,
+    }
+
+    impl PieceTable {
+        pub fn new(buffer: &str) -> PieceTable {
+            PieceTable {
+                original: buffer.to_string(),
+                pieces: vec![Piece {
+                    offset: 0,
+                    length: buffer.len(),
+                }],
+            }
+        }
+
+        pub fn get_lines(&self, from: usize, to: usize) -> Segment {
+            let mut current_line = 1;
+            let mut res = Segment::new();
+            let mut line_value = String::new();
+            let mut current_offset = 0;
+
+            for piece in self.pieces.iter() {
+                let segment = &self.original[piece.offset..piece.offset + piece.length];
+
+                for char in segment.chars() {
+                    current_offset += char.len_utf8();
+                    if current_line >= from && to >= current_line {
+                        line_value.push(char);
+                    }
+
+                    if char == '\n' {
+                        if current_line >= from && to >= current_line {
+                            res.new_b(line_value.clone(), current_line, current_offset);
+                            line_value.clear();
+                        }
+                        current_line += 1;
+                    }
+
+                    if current_line > to {
+                        break;
+                    }
+                }
+            }
+
+            res
+        }
+
+        pub fn next_line(&self, segment: &mut Segment) {
+            if let Some(last_node) = segment.back() {
+                if let Some(next_line) = self
+                    .get_lines(last_node.line_number + 1, last_node.line_number + 1)
+                    .front()
+                    .cloned()
+                {
+                    segment.pop_f();
+                    segment.add_b(next_line);
+                }
+            }
+        }
+    }
+}
+
+fn document() -> String {
+    (1..=LINES)
+        .map(|i| format!("{} the quick brown fox jumps over the lazy dog\n", i))
+        .collect()
+}
+
+/// tree with many small pieces, like after a long editing session
+fn edited_table(doc: &str) -> PieceTable {
+    let mut table = PieceTable::new(doc);
+    let step = doc.len() / 2_000;
+    for i in 0..2_000 {
+        table.insert("x", i * step + i);
+    }
+    table
+}
+
+fn get_lines(c: &mut Criterion) {
+    let doc = document();
+    let vec_table = vec_table::PieceTable::new(&doc);
+    let tree_table = PieceTable::new(&doc);
+    let edited = edited_table(&doc);
+
+    let mut group = c.benchmark_group("get_lines");
+    for from in [1, LINES / 2, LINES - VIEWPORT] {
+        group.bench_with_input(BenchmarkId::new("vec", from), &from, |b, &from| {
+            b.iter(|| vec_table.get_lines(black_box(from), from + VIEWPORT))
+        });
+        group.bench_with_input(BenchmarkId::new("tree", from), &from, |b, &from| {
+            b.iter(|| tree_table.get_lines(black_box(from), from + VIEWPORT))
+        });
+        group.bench_with_input(BenchmarkId::new("tree_edited", from), &from, |b, &from| {
+            b.iter(|| edited.get_lines(black_box(from), from + VIEWPORT))
+        });
+    }
+    group.finish();
+}
+
+fn scroll(c: &mut Criterion) {
+    let doc = document();
+    let vec_table = vec_table::PieceTable::new(&doc);
+    let tree_table = PieceTable::new(&doc);
+    let from = LINES / 2;
+
+    let mut group = c.benchmark_group("scroll_100_lines");
+    group.bench_function("vec", |b| {
+        b.iter(|| {
+            let mut segment: Segment = vec_table.get_lines(from, from + VIEWPORT);
+            for _ in 0..100 {
+                vec_table.next_line(&mut segment);
+            }
+            segment
+        })
+    });
+    group.bench_function("tree", |b| {
+        b.iter(|| {
+            let mut segment = tree_table.get_lines(from, from + VIEWPORT);
+            for _ in 0..100 {
+                tree_table.next_line(&mut segment, VIEWPORT + 1, |_| 1);
+            }
+            segment
+        })
+    });
+    group.finish();
+}
+
+fn lookups(c: &mut Criterion) {
+    let doc = document();
+    let edited = edited_table(&doc);
+
+    let mut group = c.benchmark_group("lookup");
+    group.bench_function("line_offset", |b| {
+        b.iter(|| edited.line_offset(black_box(LINES - 10)))
+    });
+    group.bench_function("line_at", |b| {
+        b.iter(|| edited.line_at(black_box(doc.len() - 100)))
+    });
+    group.bench_function("insert", |b| {
+        b.iter_batched(
+            || edited_table(&doc),
+            |mut table| {
+                table.insert("y", black_box(doc.len() / 3));
+                table
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, get_lines, scroll, lookups);
+criterion_main!(benches);
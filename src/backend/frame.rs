@@ -0,0 +1,215 @@
+use crate::backend::{Backend, Cell, Clear, CursorShape, Style};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// cells of the whole screen with the cursor, it's drawn into the same way as a terminal.
+/// positions are (x, y) and start from 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub size: (u16, u16),
+    rows: Vec<Vec<Cell>>,
+    pub cursor: (u16, u16),
+    pub shape: CursorShape,
+}
+
+impl Frame {
+    pub fn new(size: (u16, u16)) -> Frame {
+        Frame {
+            size,
+            rows: vec![vec![Cell::blank(); usize::from(size.0)]; usize::from(size.1)],
+            cursor: (1, 1),
+            shape: CursorShape::Block,
+        }
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<&Cell> {
+        if x == 0 || y == 0 {
+            return None;
+        }
+        self.rows.get(usize::from(y) - 1)?.get(usize::from(x) - 1)
+    }
+
+    fn cell_mut(&mut self, x: u16, y: u16) -> Option<&mut Cell> {
+        if x == 0 || y == 0 {
+            return None;
+        }
+        self.rows
+            .get_mut(usize::from(y) - 1)?
+            .get_mut(usize::from(x) - 1)
+    }
+
+    /// text of the row without white space at the end
+    pub fn line(&self, y: u16) -> String {
+        let text: String = match self.rows.get(usize::from(y).saturating_sub(1)) {
+            Some(row) => row.iter().map(|c| c.text.as_str()).collect(),
+            None => String::new(),
+        };
+        text.trim_end().to_string()
+    }
+
+    pub fn goto(&mut self, x: u16, y: u16) {
+        self.cursor = (x.clamp(1, self.size.0), y.clamp(1, self.size.1));
+    }
+
+    pub fn clear(&mut self, clear: Clear) {
+        let (x, y) = self.cursor;
+        match clear {
+            Clear::All => {
+                for y in 1..=self.size.1 {
+                    self.clear_row(y, 1);
+                }
+            }
+            Clear::CurrentLine => self.clear_row(y, 1),
+            Clear::AfterCursor => {
+                self.clear_row(y, x);
+                for below in y + 1..=self.size.1 {
+                    self.clear_row(below, 1);
+                }
+            }
+        }
+    }
+
+    fn clear_row(&mut self, y: u16, from: u16) {
+        for x in from..=self.size.0 {
+            if let Some(cell) = self.cell_mut(x, y) {
+                *cell = Cell::blank();
+            }
+        }
+    }
+
+    /// prints the text at the cursor, a line break moves to the start of the next row and text
+    /// past the right edge is dropped
+    pub fn print(&mut self, text: &str, style: Style) {
+        for g in text.graphemes(true) {
+            match g {
+                "\n" | "\r\n" => self.cursor = (1, (self.cursor.1 + 1).min(self.size.1)),
+                "\r" => self.cursor.0 = 1,
+                _ => self.put(g, style),
+            }
+        }
+    }
+
+    fn put(&mut self, grapheme: &str, style: Style) {
+        let (x, y) = self.cursor;
+        let width = grapheme.width();
+
+        // marks that don't take space join the grapheme before them
+        if width == 0 {
+            if let Some(cell) = self.cell_mut(x.saturating_sub(1), y) {
+                cell.text.push_str(grapheme);
+            }
+            return;
+        }
+
+        if usize::from(x) + width - 1 > usize::from(self.size.0) {
+            self.cursor.0 = self.size.0 + 1;
+            return;
+        }
+
+        // a wide grapheme that is partly overwritten is gone
+        if self.cell(x, y).is_some_and(|c| c.text.is_empty()) {
+            if let Some(cell) = self.cell_mut(x - 1, y) {
+                *cell = Cell::blank();
+            }
+        }
+        let end = x + width as u16;
+        if self.cell(end, y).is_some_and(|c| c.text.is_empty()) {
+            if let Some(cell) = self.cell_mut(end, y) {
+                *cell = Cell::blank();
+            }
+        }
+
+        for i in 0..width as u16 {
+            let text = if i == 0 { grapheme } else { "" };
+            if let Some(cell) = self.cell_mut(x + i, y) {
+                *cell = Cell {
+                    text: text.to_string(),
+                    style,
+                };
+            }
+        }
+
+        self.cursor.0 = end;
+    }
+}
+
+/// draws frames on the backend, only cells that differ from the frame drawn last are printed
+#[derive(Debug)]
+pub struct Renderer {
+    last: Option<Frame>,
+}
+
+impl Default for Renderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer {
+    pub fn new() -> Renderer {
+        Renderer { last: None }
+    }
+
+    /// the next frame is drawn from scratch, e.g. when the screen may have been changed by
+    /// something else
+    pub fn invalidate(&mut self) {
+        self.last = None;
+    }
+
+    pub fn render(&mut self, frame: &Frame, backend: &mut dyn Backend) {
+        let last = match self.last.take() {
+            Some(last) if last.size == frame.size => last,
+            _ => {
+                backend.clear(Clear::All);
+                backend.cursor_shape(frame.shape);
+                let mut blank = Frame::new(frame.size);
+                blank.shape = frame.shape;
+                blank
+            }
+        };
+
+        let mut printed = false;
+        for y in 1..=frame.size.1 {
+            let mut x = 1;
+            while x <= frame.size.0 {
+                if !damaged(&last, frame, x, y) {
+                    x += 1;
+                    continue;
+                }
+
+                // changed cells next to each other with the same style are printed at once
+                let start = x;
+                let style = frame.cell(x, y).map_or(Style::Normal, |c| c.style);
+                let mut text = String::new();
+                while x <= frame.size.0 && damaged(&last, frame, x, y) {
+                    match frame.cell(x, y) {
+                        Some(cell) if cell.style == style => text.push_str(&cell.text),
+                        _ => break,
+                    }
+                    x += 1;
+                }
+
+                backend.goto(start, y);
+                backend.print(&text, style);
+                printed = true;
+            }
+        }
+
+        if frame.shape != last.shape {
+            backend.cursor_shape(frame.shape);
+        }
+        if printed || frame.cursor != last.cursor {
+            backend.goto(frame.cursor.0, frame.cursor.1);
+        }
+
+        self.last = Some(frame.clone());
+    }
+}
+
+/// the cell differs from the last frame, a wide grapheme is damaged with the empty cell after
+/// it as they are printed together
+fn damaged(last: &Frame, frame: &Frame, x: u16, y: u16) -> bool {
+    let next = frame.cell(x + 1, y);
+    let wide = next.is_some_and(|c| c.text.is_empty()) && last.cell(x + 1, y) != next;
+    last.cell(x, y) != frame.cell(x, y) || wide
+}
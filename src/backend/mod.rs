@@ -0,0 +1,68 @@
+pub mod frame;
+pub mod screen;
+pub mod terminal;
+
+/// how a piece of text looks on the screen
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Style {
+    #[default]
+    Normal,
+    /// selected in visual mode
+    Selection,
+    /// match of the highlighted pattern
+    Match,
+    Error,
+    /// number of a line in the gutter
+    LineNr,
+    /// number of the line the cursor is on
+    CurrentLineNr,
+    StatusLine,
+    /// shown on the screen but not a part of the text, like showbreak
+    NonText,
+}
+
+/// one place on the screen, the right half of a wide grapheme is an empty cell
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Cell {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Cell {
+    pub fn blank() -> Cell {
+        Cell {
+            text: " ".to_string(),
+            style: Style::Normal,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clear {
+    All,
+    CurrentLine,
+    /// rest of the line and lines below it
+    AfterCursor,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Bar,
+}
+
+/// where the editor is drawn, a real terminal or a screen kept in memory.
+/// positions are (x, y) and start from 1.
+pub trait Backend {
+    /// (width, height) of the screen
+    fn size(&self) -> (u16, u16);
+    fn goto(&mut self, x: u16, y: u16);
+    fn clear(&mut self, clear: Clear);
+    /// prints the text at the cursor, a line break moves to the start of the next line
+    fn print(&mut self, text: &str, style: Style);
+    fn cursor_shape(&mut self, shape: CursorShape);
+    /// puts the text into the system clipboard, selection is the OSC 52 one
+    fn set_clipboard(&mut self, selection: char, text: &str);
+    fn flush(&mut self);
+}
@@ -0,0 +1,110 @@
+use crate::backend::frame::Frame;
+use crate::backend::{Backend, Cell, Clear, CursorShape, Style};
+use std::cell::RefCell;
+use std::rc::Rc;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug)]
+struct State {
+    frame: Frame,
+    clipboard: Option<(char, String)>,
+    /// count of graphemes printed so far
+    printed: usize,
+}
+
+/// screen kept in memory instead of a terminal, it records cells, the cursor and it's shape
+/// so tests can look at what the editor has drawn. clones share the same screen.
+#[derive(Clone, Debug)]
+pub struct Screen {
+    state: Rc<RefCell<State>>,
+}
+
+impl Screen {
+    pub fn new(width: u16, height: u16) -> Screen {
+        Screen {
+            state: Rc::new(RefCell::new(State {
+                frame: Frame::new((width, height)),
+                clipboard: None,
+                printed: 0,
+            })),
+        }
+    }
+
+    /// changes the size like a resized terminal does, the content is cleared
+    pub fn resize(&self, width: u16, height: u16) {
+        let mut state = self.state.borrow_mut();
+        let shape = state.frame.shape;
+        state.frame = Frame::new((width, height));
+        state.frame.shape = shape;
+    }
+
+    /// text of the row without white space at the end, rows start from 1
+    pub fn line(&self, y: u16) -> String {
+        self.state.borrow().frame.line(y)
+    }
+
+    /// text of all rows
+    pub fn lines(&self) -> Vec<String> {
+        let state = self.state.borrow();
+        (1..=state.frame.size.1)
+            .map(|y| state.frame.line(y))
+            .collect()
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Cell {
+        self.state
+            .borrow()
+            .frame
+            .cell(x, y)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn cursor(&self) -> (u16, u16) {
+        self.state.borrow().frame.cursor
+    }
+
+    pub fn shape(&self) -> CursorShape {
+        self.state.borrow().frame.shape
+    }
+
+    /// selection and text last sent to the system clipboard
+    pub fn clipboard(&self) -> Option<(char, String)> {
+        self.state.borrow().clipboard.clone()
+    }
+
+    /// count of graphemes printed since the screen was made, it shows how much was drawn
+    pub fn printed(&self) -> usize {
+        self.state.borrow().printed
+    }
+}
+
+impl Backend for Screen {
+    fn size(&self) -> (u16, u16) {
+        self.state.borrow().frame.size
+    }
+
+    fn goto(&mut self, x: u16, y: u16) {
+        self.state.borrow_mut().frame.goto(x, y);
+    }
+
+    fn clear(&mut self, clear: Clear) {
+        self.state.borrow_mut().frame.clear(clear);
+    }
+
+    fn print(&mut self, text: &str, style: Style) {
+        let mut state = self.state.borrow_mut();
+        state.printed += text.graphemes(true).count();
+        state.frame.print(text, style);
+    }
+
+    fn cursor_shape(&mut self, shape: CursorShape) {
+        self.state.borrow_mut().frame.shape = shape;
+    }
+
+    fn set_clipboard(&mut self, selection: char, text: &str) {
+        self.state.borrow_mut().clipboard = Some((selection, text.to_string()));
+    }
+
+    fn flush(&mut self) {}
+}
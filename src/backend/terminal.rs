@@ -0,0 +1,110 @@
+use crate::backend::{Backend, Clear, CursorShape, Style};
+use crate::clipboard;
+use crate::codes;
+use std::io::{self, Stdout, Write};
+use termion::raw::{IntoRawMode, RawTerminal};
+use termion::{clear, color, cursor, style};
+
+/// terminal the editor runs in, it's kept in raw mode with bracketed paste on until dropped
+pub struct Terminal {
+    stdout: RawTerminal<Stdout>,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        write!(self.stdout, "{}", codes::PASTE_OFF).unwrap();
+        self.stdout.flush().unwrap();
+    }
+}
+
+impl Terminal {
+    pub fn new() -> Terminal {
+        let mut stdout = io::stdout().into_raw_mode().unwrap();
+        write!(stdout, "{}", codes::PASTE_ON).unwrap();
+
+        Terminal { stdout }
+    }
+}
+
+impl Backend for Terminal {
+    fn size(&self) -> (u16, u16) {
+        termion::terminal_size().unwrap()
+    }
+
+    fn goto(&mut self, x: u16, y: u16) {
+        write!(self.stdout, "{}", cursor::Goto(x.max(1), y.max(1))).unwrap();
+    }
+
+    fn clear(&mut self, kind: Clear) {
+        match kind {
+            Clear::All => write!(self.stdout, "{}", clear::All),
+            Clear::CurrentLine => write!(self.stdout, "{}", clear::CurrentLine),
+            Clear::AfterCursor => write!(self.stdout, "{}", clear::AfterCursor),
+        }
+        .unwrap();
+    }
+
+    fn print(&mut self, text: &str, kind: Style) {
+        // raw mode doesn't return to the start of the line on a line break
+        let text = text.replace('\n', "\r\n");
+        match kind {
+            Style::Normal => write!(self.stdout, "{}", text),
+            Style::Selection | Style::StatusLine => {
+                write!(self.stdout, "{}{}{}", style::Invert, text, style::Reset)
+            }
+            Style::Match => write!(
+                self.stdout,
+                "{}{}{}{}",
+                color::Bg(color::Yellow),
+                color::Fg(color::Black),
+                text,
+                style::Reset
+            ),
+            Style::Error => write!(
+                self.stdout,
+                "{}{}{}",
+                color::Fg(color::Red),
+                text,
+                color::Fg(color::Reset)
+            ),
+            Style::LineNr | Style::NonText => write!(
+                self.stdout,
+                "{}{}{}",
+                color::Fg(color::LightBlack),
+                text,
+                color::Fg(color::Reset)
+            ),
+            Style::CurrentLineNr => write!(
+                self.stdout,
+                "{}{}{}{}",
+                style::Bold,
+                color::Fg(color::Yellow),
+                text,
+                style::Reset
+            ),
+        }
+        .unwrap();
+    }
+
+    fn cursor_shape(&mut self, shape: CursorShape) {
+        match shape {
+            CursorShape::Block => write!(self.stdout, "{}", cursor::SteadyBlock),
+            CursorShape::Bar => write!(self.stdout, "{}", cursor::SteadyBar),
+        }
+        .unwrap();
+    }
+
+    fn set_clipboard(&mut self, selection: char, text: &str) {
+        clipboard::write_osc52(&mut self.stdout, selection, text).unwrap();
+    }
+
+    fn flush(&mut self) {
+        self.stdout.flush().unwrap();
+    }
+}
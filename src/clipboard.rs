@@ -0,0 +1,71 @@
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::{self, Read, Write};
+use std::process::{Command, Stdio};
+
+/// OSC 52 selection the register is kept in, `"+` is the clipboard and `"*` the primary
+/// selection
+pub fn selection(register: char) -> char {
+    if register == '*' {
+        'p'
+    } else {
+        'c'
+    }
+}
+
+/// asks the terminal to put the text into the system clipboard with the OSC 52 escape, it
+/// works over ssh as the terminal on the local machine handles it
+pub fn write_osc52<W: Write>(out: &mut W, selection: char, text: &str) -> io::Result<()> {
+    write!(out, "\x1b]52;{};{}\x07", selection, STANDARD.encode(text))?;
+    out.flush()
+}
+
+/// runs the command, e.g. `wl-copy` or `xclip -selection clipboard`, with the text on its
+/// input
+pub fn copy(command: &str, text: &str) -> Result<(), String> {
+    let mut child = spawn(command, Stdio::piped(), Stdio::null())?;
+    if let Some(mut input) = child.stdin.take() {
+        input
+            .write_all(text.as_bytes())
+            .map_err(|e| format!("{}: {}", command, e))?;
+    }
+
+    match child.wait() {
+        Ok(status) if status.success() => Ok(()),
+        Ok(status) => Err(format!("{}: {}", command, status)),
+        Err(e) => Err(format!("{}: {}", command, e)),
+    }
+}
+
+/// runs the command, e.g. `wl-paste -n` or `xclip -o -selection clipboard`, and returns
+/// what it prints
+pub fn paste(command: &str) -> Result<String, String> {
+    let mut child = spawn(command, Stdio::null(), Stdio::piped())?;
+    let mut text = String::new();
+    if let Some(mut output) = child.stdout.take() {
+        output
+            .read_to_string(&mut text)
+            .map_err(|e| format!("{}: {}", command, e))?;
+    }
+
+    match child.wait() {
+        Ok(status) if status.success() => Ok(text),
+        Ok(status) => Err(format!("{}: {}", command, status)),
+        Err(e) => Err(format!("{}: {}", command, e)),
+    }
+}
+
+/// the command is split on white space and run without a shell, only the pipe the text goes
+/// through is open so the command can't wait on the other one
+fn spawn(command: &str, input: Stdio, output: Stdio) -> Result<std::process::Child, String> {
+    let mut args = command.split_whitespace();
+    let program = args.next().ok_or("No clipboard command")?;
+
+    Command::new(program)
+        .args(args)
+        .stdin(input)
+        .stdout(output)
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("{}: {}", program, e))
+}
@@ -1,2 +1,9 @@
 pub const BACKSPACE: char = '\x08';
 pub const RETURN: char = '\n';
+pub const DELETE: char = '\x7f';
+
+/// terminal wraps pasted text in these when bracketed paste is on, so it's not typed key by key
+pub const PASTE_ON: &str = "\x1b[?2004h";
+pub const PASTE_OFF: &str = "\x1b[?2004l";
+pub const PASTE_START: &[u8] = b"\x1b[200~";
+pub const PASTE_END: &[u8] = b"\x1b[201~";
@@ -0,0 +1,307 @@
+/// command typed in the command line
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Write {
+        path: Option<String>,
+        force: bool,
+    },
+    Quit {
+        force: bool,
+    },
+    WriteQuit {
+        path: Option<String>,
+        force: bool,
+    },
+    /// writes only when there are changes, then quits
+    Exit {
+        path: Option<String>,
+        force: bool,
+    },
+    Edit {
+        path: Option<String>,
+        force: bool,
+    },
+    SaveAs {
+        path: String,
+        force: bool,
+    },
+    Goto(usize),
+    Set(Vec<String>),
+    Substitute(Substitute),
+    /// stops highlighting matches of the last search
+    NoHighlight,
+    /// lists registers that are not empty
+    Registers,
+}
+
+/// line in a command range
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Address {
+    Line(usize),
+    /// `.`, line with the cursor
+    Current,
+    /// `$`
+    Last,
+    /// `'<` or `'>`, first or last line of the last selection
+    Mark(char),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Range {
+    pub from: Address,
+    pub to: Address,
+}
+
+/// `:[range]s/pattern/replacement/[flags]`, without a range it works on the current line
+#[derive(Clone, Debug, PartialEq)]
+pub struct Substitute {
+    pub range: Option<Range>,
+    pub pattern: String,
+    pub replacement: String,
+    /// every match in a line is replaced, not only the first one
+    pub global: bool,
+    /// each replacement is confirmed
+    pub confirm: bool,
+    pub ignore_case: bool,
+}
+
+/// full command names with the shortest accepted abbreviation
+const COMMANDS: &[(&str, usize)] = &[
+    ("write", 1),
+    ("quit", 1),
+    ("wq", 2),
+    ("xit", 1),
+    ("exit", 3),
+    ("edit", 1),
+    ("saveas", 3),
+    ("set", 2),
+    ("substitute", 1),
+    ("nohlsearch", 3),
+    ("registers", 3),
+    ("display", 2),
+];
+
+fn full_name(name: &str) -> Option<&'static str> {
+    COMMANDS
+        .iter()
+        .find(|(full, min)| name.len() >= *min && full.starts_with(name))
+        .map(|(full, _)| *full)
+}
+
+/// parses the command line input, e.g. `w file.txt`, `q!`, `42` or `%s/a/b/g`
+pub fn parse(input: &str) -> Result<Command, String> {
+    let input = input.trim_start_matches(|c: char| c == ':' || c.is_whitespace());
+    let input = input.trim_end();
+
+    let (range, input) = parse_range(input)?;
+
+    if input.is_empty() {
+        return match range.map(|r| r.to) {
+            Some(Address::Line(ln)) => Ok(Command::Goto(ln)),
+            Some(Address::Last) => Ok(Command::Goto(usize::MAX)),
+            _ => Err(String::new()),
+        };
+    }
+
+    let name_len = input
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(input.len());
+    let (name, rest) = input.split_at(name_len);
+
+    let full = match full_name(name) {
+        Some(f) => f,
+        None => return Err(format!("Not an editor command: {}", input)),
+    };
+
+    if full == "substitute" {
+        return parse_substitute(range, rest).map(Command::Substitute);
+    }
+    if range.is_some() {
+        return Err(String::from("No range allowed"));
+    }
+
+    let (force, rest) = match rest.strip_prefix('!') {
+        Some(r) => (true, r),
+        None => (false, rest),
+    };
+    let arg = rest.trim();
+    let path = if arg.is_empty() {
+        None
+    } else {
+        Some(arg.to_string())
+    };
+
+    let no_args = |cmd: Command| {
+        if arg.is_empty() {
+            Ok(cmd)
+        } else {
+            Err(format!("Trailing characters: {}", arg))
+        }
+    };
+
+    match full {
+        "write" => Ok(Command::Write { path, force }),
+        "quit" => no_args(Command::Quit { force }),
+        "wq" => Ok(Command::WriteQuit { path, force }),
+        "xit" | "exit" => Ok(Command::Exit { path, force }),
+        "edit" => Ok(Command::Edit { path, force }),
+        "saveas" => match path {
+            Some(path) => Ok(Command::SaveAs { path, force }),
+            None => Err(String::from("Argument required")),
+        },
+        "nohlsearch" => no_args(Command::NoHighlight),
+        "registers" | "display" => no_args(Command::Registers),
+        "set" => Ok(Command::Set(set_args(arg))),
+        _ => Err(format!("Not an editor command: {}", input)),
+    }
+}
+
+/// `:set` arguments are split on white space, `\ ` keeps a space in the value
+fn set_args(input: &str) -> Vec<String> {
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek().is_some_and(|n| n.is_whitespace()) => {
+                arg.extend(chars.next());
+            }
+            c if c.is_whitespace() => {
+                if !arg.is_empty() {
+                    args.push(std::mem::take(&mut arg));
+                }
+            }
+            c => arg.push(c),
+        }
+    }
+    if !arg.is_empty() {
+        args.push(arg);
+    }
+
+    args
+}
+
+fn parse_address(input: &str) -> Result<(Option<Address>, &str), String> {
+    if let Some(rest) = input.strip_prefix('.') {
+        return Ok((Some(Address::Current), rest));
+    }
+    if let Some(rest) = input.strip_prefix('$') {
+        return Ok((Some(Address::Last), rest));
+    }
+    if let Some(rest) = input.strip_prefix('\'') {
+        let mut chars = rest.chars();
+        return match chars.next() {
+            Some(mark @ ('<' | '>')) => Ok((Some(Address::Mark(mark)), chars.as_str())),
+            _ => Err(String::from("Invalid range")),
+        };
+    }
+
+    let digits = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    if digits == 0 {
+        return Ok((None, input));
+    }
+
+    let ln = input[..digits]
+        .parse()
+        .map_err(|_| String::from("Invalid range"))?;
+    Ok((Some(Address::Line(ln)), &input[digits..]))
+}
+
+/// `%`, `address` or `address,address` at the start of the input
+fn parse_range(input: &str) -> Result<(Option<Range>, &str), String> {
+    if let Some(rest) = input.strip_prefix('%') {
+        let range = Range {
+            from: Address::Line(1),
+            to: Address::Last,
+        };
+        return Ok((Some(range), rest.trim_start()));
+    }
+
+    let (from, rest) = match parse_address(input)? {
+        (Some(from), rest) => (from, rest),
+        (None, rest) => return Ok((None, rest)),
+    };
+
+    let (to, rest) = match rest.strip_prefix(',') {
+        Some(rest) => match parse_address(rest)? {
+            (Some(to), rest) => (to, rest),
+            (None, _) => return Err(String::from("Invalid range")),
+        },
+        None => (from, rest),
+    };
+
+    Ok((Some(Range { from, to }), rest.trim_start()))
+}
+
+/// splits `/pattern/replacement/flags`, any punctuation may be used instead of `/` and escaped
+/// with a backslash within the pattern or the replacement
+fn parse_substitute(range: Option<Range>, input: &str) -> Result<Substitute, String> {
+    let mut chars = input.chars();
+    let delimiter = match chars.next() {
+        Some(c) if c.is_ascii_punctuation() && c != '\\' && c != '"' => c,
+        Some(_) => {
+            return Err(String::from(
+                "Regular expressions can't be delimited by letters",
+            ))
+        }
+        None => return Err(String::from("Argument required")),
+    };
+
+    let mut parts = vec![String::new()];
+    while let Some(c) = chars.next() {
+        let in_flags = parts.len() == 3;
+        if c == delimiter && !in_flags {
+            parts.push(String::new());
+            continue;
+        }
+
+        let in_pattern = parts.len() == 1;
+        let part = parts.last_mut().unwrap();
+        if c == '\\' && !in_flags {
+            match chars.next() {
+                // escaped delimiter in the pattern matches the delimiter itself, escapes in the
+                // replacement are handled when it's expanded
+                Some(next) if next == delimiter && in_pattern => {
+                    part.push_str(&regex::escape(&next.to_string()))
+                }
+                Some(next) => {
+                    part.push('\\');
+                    part.push(next);
+                }
+                None => part.push('\\'),
+            }
+            continue;
+        }
+
+        part.push(c);
+    }
+
+    let mut parts = parts.into_iter();
+    let pattern = parts.next().unwrap_or_default();
+    let replacement = parts.next().unwrap_or_default();
+    let flags = parts.next().unwrap_or_default();
+
+    let mut cmd = Substitute {
+        range,
+        pattern,
+        replacement,
+        global: false,
+        confirm: false,
+        ignore_case: false,
+    };
+
+    for flag in flags.trim().chars() {
+        match flag {
+            'g' => cmd.global = true,
+            'c' => cmd.confirm = true,
+            'i' => cmd.ignore_case = true,
+            'I' => cmd.ignore_case = false,
+            _ => return Err(format!("Trailing characters: {}", flags.trim())),
+        }
+    }
+
+    Ok(cmd)
+}
@@ -1 +0,0 @@
-pub const TABULATION_COUNT: u16 = 8;
@@ -1,78 +1,1200 @@
+use crate::backend::terminal::Terminal;
+use crate::clipboard;
 use crate::codes;
+use crate::command::{self, Address, Command, Substitute};
+use crate::input::Input;
+use crate::keys;
 use crate::motion::Motions;
+use crate::normal::{Action, Operator, Parsed, Parser};
+use crate::prompt::{Prompt, PromptKind, PromptResult};
+use crate::register::{Register, Registers, Store};
+use crate::status::{self, Status};
+use crate::stdio::Stdio;
 use crate::window::buffer::Buffer;
-use std::io::{Stdin, Write};
+use crate::window::search::{self, Direction, Pattern, Replacement};
+use crate::window::selection::SelectionKind;
+use std::sync::mpsc::Receiver;
 use termion::event::{Event, Key};
 
-use termion::input::TermRead;
+use unicode_width::UnicodeWidthStr;
+
 pub enum EditorModes {
     Normal,
     Insert,
+    Visual,
+    VisualLine,
+    VisualBlock,
+    Command,
+}
+
+/// substitution that waits for each replacement to be confirmed
+struct Confirm {
+    replacements: Vec<Replacement>,
+    /// index of the replacement that is asked about
+    next: usize,
+    /// change of the document length made by replacements done so far
+    shift: isize,
+    accepted: Vec<Replacement>,
+}
+
+/// what is typed in insert mode, replayed by `.`
+#[derive(Clone, Debug)]
+enum Typed {
+    Key(char),
+    Paste(String),
+}
+
+/// last change repeated by `.`, the normal mode command and what was typed in insert mode
+/// after it
+#[derive(Clone, Debug)]
+struct Change {
+    action: Action,
+    register: Option<char>,
+    typed: Vec<Typed>,
 }
 
+/// registers played from within played registers stop nesting at this depth
+const MAX_PLAY_DEPTH: usize = 100;
+
 pub struct Editor {
     mode: EditorModes,
+    /// keys of a normal mode command typed so far
+    keys: Parser,
+    prompt: Option<Prompt>,
+    confirm: Option<Confirm>,
+    command_history: Vec<String>,
+    search_history: Vec<String>,
+    /// last searched pattern, repeated with n and N
+    search: Option<(String, Direction)>,
+    /// position where the cursor returns when the search is cancelled
+    search_origin: (usize, usize),
+    registers: Registers,
+    /// text pasted so far while the terminal sends a bracketed paste
+    pasted: Option<String>,
+    /// change that is being typed in insert mode
+    change: Option<Change>,
+    last_change: Option<Change>,
+    /// register and keys typed since `q{reg}`
+    recording: Option<(char, Vec<Key>)>,
+    /// depth of registers being played, keys played are not recorded
+    playing: usize,
+    /// register played last, `@@` plays it again
+    last_played: Option<char>,
+    /// a motion or a command failed, playing a register stops
+    failed: bool,
+    quit: bool,
+}
+
+impl Default for Editor {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Editor {
     pub fn new() -> Editor {
         Editor {
             mode: EditorModes::Normal,
+            keys: Parser::new(),
+            prompt: None,
+            confirm: None,
+            command_history: Vec::new(),
+            search_history: Vec::new(),
+            search: None,
+            search_origin: (1, 1),
+            registers: Registers::new(),
+            pasted: None,
+            change: None,
+            last_change: None,
+            recording: None,
+            playing: 0,
+            last_played: None,
+            failed: false,
+            quit: false,
+        }
+    }
+
+    pub fn run(&mut self, path: Option<String>, input: Receiver<Input>) {
+        let stdio = Stdio::new(Box::new(Terminal::new()));
+        let mut buffer = Buffer::new(path, stdio);
+        self.draw_status(&mut buffer);
+        buffer.stdio.flush();
+
+        for i in input.iter() {
+            self.handle_input(&mut buffer, i);
+            if self.quit {
+                break;
+            }
         }
     }
 
-    pub fn run(&mut self, path: Option<String>, stdin: Stdin) {
-        let mut buffer = Buffer::new(path);
+    /// the editor was asked to quit, no more input is handled by run
+    pub fn has_quit(&self) -> bool {
+        self.quit
+    }
+
+    /// handles one input and draws the frame made by it
+    pub fn handle_input(&mut self, buffer: &mut Buffer, input: Input) {
+        match input {
+            Input::Event(evt) => self.handle(buffer, evt),
+            Input::Resize(size) => self.resize(buffer, size),
+            Input::Closed => self.quit = true,
+        }
+        self.draw_status(buffer);
+        buffer.stdio.flush();
+    }
 
-        for c in stdin.events() {
-            let evt = c.unwrap();
+    /// status line made from the statusline option
+    fn draw_status(&self, buffer: &mut Buffer) {
+        let mode = match self.mode {
+            EditorModes::Normal => "NORMAL",
+            EditorModes::Insert => "INSERT",
+            EditorModes::Visual => "VISUAL",
+            EditorModes::VisualLine => "V-LINE",
+            EditorModes::VisualBlock => "V-BLOCK",
+            EditorModes::Command => "COMMAND",
+        };
+        let file = buffer.file_name();
+        let (line, col) = buffer.position();
+        let info = Status {
+            mode,
+            file: &file,
+            modified: buffer.modified,
+            filetype: status::filetype(&file),
+            line,
+            col,
+            lines: buffer.data.line_count(),
+            line_ending: buffer.line_ending,
+            encoding: "utf-8",
+        };
 
+        let width = usize::from(buffer.stdio.terminal_size.0);
+        let text = status::render(&buffer.options.statusline, &info, width);
+        buffer.stdio.display_status(&text);
+    }
+
+    /// everything is drawn again for the new size, an open prompt stays where it was typed
+    fn resize(&mut self, buffer: &mut Buffer, size: (u16, u16)) {
+        buffer.resize(size);
+
+        if self.confirm.is_some() {
+            self.ask_confirm(buffer);
+        } else if let Some(prompt) = self.prompt.as_ref() {
+            buffer
+                .stdio
+                .display_prompt(&prompt.label, &prompt.text(), prompt.cursor_col());
+        }
+    }
+
+    /// handles one event, keys played from a register come here as well as typed ones
+    fn handle(&mut self, buffer: &mut Buffer, evt: Event) {
+        let recording = self.recording.as_mut().filter(|_| self.playing == 0);
+        if let (Some((_, keys)), Event::Key(key), None) = (recording, &evt, &self.pasted) {
+            keys.push(*key);
+        }
+
+        // pasted text is collected and inserted at once when the paste ends
+        if let Some(pasted) = self.pasted.as_mut() {
             match evt {
-                Event::Key(Key::Ctrl('q')) => break,
-                Event::Key(Key::Ctrl('s')) => {
+                Event::Unsupported(seq) if seq == codes::PASTE_END => {
+                    let text = self.pasted.take().unwrap_or_default();
+                    self.record_paste(&text);
+                    self.paste(buffer, &text);
+                }
+                Event::Key(Key::Char(ch)) => pasted.push(ch),
+                _ => {}
+            }
+            return;
+        }
+        if matches!(&evt, Event::Unsupported(seq) if seq == codes::PASTE_START) {
+            self.pasted = Some(String::new());
+            return;
+        }
+
+        if self.confirm.is_some() {
+            self.confirm_input(buffer, evt);
+            return;
+        }
+
+        if self.prompt.is_some() {
+            self.quit = self.prompt_input(buffer, evt);
+            return;
+        }
+
+        if self.selection_kind().is_some() {
+            self.visual_input(buffer, evt);
+            return;
+        }
+
+        // any other key drops a command that is being typed
+        if !matches!(evt, Event::Key(Key::Char(_))) && !self.keys.pending().is_empty() {
+            self.keys.clear();
+            buffer.set_pending("");
+        }
+
+        match evt {
+            Event::Key(Key::Ctrl('q')) => self.quit = true,
+            Event::Key(Key::Ctrl('s')) => {
+                if buffer.has_file_path() {
                     buffer.save();
+                } else {
+                    self.open_prompt(buffer, Prompt::new(PromptKind::SaveAs, "Save as: "));
+                }
+            }
+            Event::Key(Key::Left) => self.insert_motion(buffer, Motions::Left),
+            Event::Key(Key::Right) => self.insert_motion(buffer, Motions::Right),
+            Event::Key(Key::Up) => self.insert_motion(buffer, Motions::Up),
+            Event::Key(Key::Down) => self.insert_motion(buffer, Motions::Down),
+            Event::Key(Key::Ctrl('r')) => {
+                if matches!(self.mode, EditorModes::Normal) {
+                    buffer.redo();
+                }
+            }
+            Event::Key(Key::Ctrl('v')) => {
+                if matches!(self.mode, EditorModes::Normal) {
+                    self.visual(buffer, EditorModes::VisualBlock);
+                }
+            }
+            Event::Key(Key::Esc) => {
+                if matches!(self.mode, EditorModes::Insert) {
+                    self.stop_insert(buffer);
+                }
+                self.mode = EditorModes::Normal;
+                buffer.stdio.cursor_block();
+            }
+            Event::Key(Key::Backspace) => {
+                if matches!(self.mode, EditorModes::Normal) {
+                    buffer.motion(Motions::Left)
+                }
+
+                self.record(Typed::Key(codes::BACKSPACE));
+                buffer.edit(codes::BACKSPACE);
+            }
+            Event::Key(Key::Delete) => {
+                self.record(Typed::Key(codes::DELETE));
+                buffer.edit(codes::DELETE);
+            }
+            Event::Key(Key::Char(ch)) => {
+                if matches!(self.mode, EditorModes::Insert) {
+                    self.record(Typed::Key(ch));
+                    buffer.edit(ch);
+                } else {
+                    match self.keys.feed(ch) {
+                        Parsed::Pending => buffer.set_pending(&self.keys.pending()),
+                        Parsed::Invalid => {
+                            self.failed = true;
+                            buffer.set_pending("");
+                        }
+                        Parsed::Done(action) => {
+                            buffer.set_pending("");
+                            self.perform(buffer, action);
+                        }
+                    }
                 }
-                Event::Key(Key::Left) => buffer.motion(Motions::Left),
-                Event::Key(Key::Right) => buffer.motion(Motions::Right),
-                Event::Key(Key::Up) => buffer.motion(Motions::Up),
-                Event::Key(Key::Down) => buffer.motion(Motions::Down),
-                Event::Key(Key::Char('i')) => match self.mode {
-                    EditorModes::Insert => {
-                        buffer.motion(Motions::Right);
-                        buffer.stdio.cursor_bar();
+            }
+            _ => {}
+        };
+    }
+
+    fn perform(&mut self, buffer: &mut Buffer, action: Action) {
+        let register = self.keys.take_register();
+
+        if let Action::Key { key: '.', count } = action {
+            self.repeat(buffer, count);
+            return;
+        }
+
+        self.run_action(buffer, action.clone(), register);
+
+        let change = Change {
+            action,
+            register,
+            typed: Vec::new(),
+        };
+        if is_change(&change.action) {
+            if matches!(self.mode, EditorModes::Insert) {
+                self.change = Some(change);
+            } else {
+                self.last_change = Some(change);
+            }
+        }
+    }
+
+    fn run_action(&mut self, buffer: &mut Buffer, action: Action, register: Option<char>) {
+        match action {
+            Action::Move { motion, count } => {
+                self.failed |= !buffer.move_by(motion, count.unwrap_or(1));
+            }
+            Action::Operate { op, motion, count } => {
+                self.operate(buffer, op, motion, count.unwrap_or(1), register)
+            }
+            Action::Key { key, count } => {
+                self.normal_key(buffer, key, count.unwrap_or(1), register)
+            }
+        }
+    }
+
+    fn operate(
+        &mut self,
+        buffer: &mut Buffer,
+        op: Operator,
+        motion: Motions,
+        count: usize,
+        register: Option<char>,
+    ) {
+        if !writable(buffer, register) {
+            return;
+        }
+
+        // deleted text and what is typed instead are undone together
+        if op == Operator::Change {
+            buffer.begin_change();
+        }
+
+        match buffer.operate(op, motion, count) {
+            Some((text, linewise)) => {
+                self.store(buffer, register, &text, linewise, op);
+                if op == Operator::Change {
+                    self.mode = EditorModes::Insert;
+                    buffer.stdio.cursor_bar();
+                }
+            }
+            None => {
+                self.failed = true;
+                if op == Operator::Change {
+                    buffer.end_change();
+                }
+            }
+        }
+    }
+
+    /// `q{reg}` starts recording typed keys into the register, `q` stops it
+    fn record_keys(&mut self, buffer: &mut Buffer, register: Option<char>) {
+        if let Some((name, mut keys)) = self.recording.take() {
+            // the `q` that stopped recording
+            keys.pop();
+            self.registers.record(name, &keys::encode(&keys));
+            self.keys.recording = false;
+            buffer.set_status("");
+            return;
+        }
+
+        let name = match register {
+            Some(name) if Registers::is_writable(Some(name)) && name != '_' => name,
+            _ => {
+                self.failed = true;
+                return;
+            }
+        };
+
+        self.recording = Some((name, Vec::new()));
+        self.keys.recording = true;
+        buffer.set_status(&format!("recording @{}", name));
+    }
+
+    /// pasted text is recorded as one paste instead of the keys it came as
+    fn record_paste(&mut self, text: &str) {
+        if let (Some((_, keys)), 0) = (self.recording.as_mut(), self.playing) {
+            keys.push(Key::Char(keys::PASTE_START));
+            keys.extend(text.chars().map(Key::Char));
+            keys.push(Key::Char(keys::PASTE_END));
+        }
+    }
+
+    /// `@{reg}` types the keys kept in the register count times, `@@` plays the last one
+    /// again. it stops when a motion or a command fails.
+    fn play(&mut self, buffer: &mut Buffer, register: Option<char>, count: usize) {
+        let name = match register {
+            Some('@') => self.last_played,
+            name => name,
+        };
+        let value = match name {
+            Some(name) => self.register(buffer, Some(name)),
+            None => None,
+        };
+        let keys = match value {
+            Some(value) if self.playing < MAX_PLAY_DEPTH => keys::decode(&value.text),
+            _ => {
+                self.failed = true;
+                return;
+            }
+        };
+
+        self.last_played = name;
+        self.playing += 1;
+        self.failed = false;
+
+        'play: for _ in 0..count {
+            for key in keys.iter() {
+                self.handle(buffer, keys::event(*key));
+                if self.failed || self.quit {
+                    break 'play;
+                }
+            }
+        }
+
+        self.playing -= 1;
+    }
+
+    /// `.` runs the last change again, a count replaces the one it was typed with
+    fn repeat(&mut self, buffer: &mut Buffer, count: Option<usize>) {
+        let mut change = match self.last_change.clone() {
+            Some(c) => c,
+            None => return,
+        };
+
+        if count.is_some() {
+            match &mut change.action {
+                Action::Operate { count: c, .. } | Action::Key { count: c, .. } => *c = count,
+                Action::Move { .. } => {}
+            }
+        }
+
+        self.run_action(buffer, change.action.clone(), change.register);
+
+        if matches!(self.mode, EditorModes::Insert) {
+            for typed in change.typed.iter() {
+                match typed {
+                    Typed::Key(ch) => buffer.edit(*ch),
+                    Typed::Paste(text) => {
+                        buffer.end_change();
+                        buffer.paste(text);
+                        buffer.begin_change();
+                    }
+                }
+            }
+            buffer.end_change();
+            self.mode = EditorModes::Normal;
+            buffer.stdio.cursor_block();
+        }
+
+        self.last_change = Some(change);
+    }
+
+    /// keeps what is typed in insert mode for the change being made
+    fn record(&mut self, typed: Typed) {
+        if !matches!(self.mode, EditorModes::Insert) {
+            return;
+        }
+        if let Some(change) = self.change.as_mut() {
+            change.typed.push(typed);
+        }
+    }
+
+    /// leaving insert mode closes the undo step and the change `.` repeats
+    fn stop_insert(&mut self, buffer: &mut Buffer) {
+        buffer.end_change();
+        if let Some(change) = self.change.take() {
+            self.last_change = Some(change);
+        }
+    }
+
+    /// keeps the text deleted, changed or yanked by the operator in the register
+    /// `"+` and `"*` go to the system clipboard as well
+    fn store(
+        &mut self,
+        buffer: &mut Buffer,
+        register: Option<char>,
+        text: &str,
+        linewise: bool,
+        op: Operator,
+    ) {
+        let store = match op {
+            Operator::Yank => Store::Yank,
+            Operator::Delete | Operator::Change => Store::Delete,
+            Operator::ShiftRight | Operator::ShiftLeft => return,
+        };
+
+        self.registers.store(register, text, linewise, store);
+
+        if let Some(name @ ('+' | '*')) = register {
+            if buffer.options.osc52 {
+                buffer.stdio.set_clipboard(clipboard::selection(name), text);
+            }
+            let command = buffer.options.clipcopy.clone();
+            if !command.is_empty() {
+                if let Err(e) = clipboard::copy(&command, text) {
+                    buffer.set_error(&e);
+                }
+            }
+        }
+    }
+
+    /// content of the register, `"+` and `"*` are read with the paste command when it's set,
+    /// otherwise what was copied from the editor is used
+    fn register(&self, buffer: &mut Buffer, register: Option<char>) -> Option<Register> {
+        let command = buffer.options.clippaste.clone();
+        if matches!(register, Some('+' | '*')) && !command.is_empty() {
+            match clipboard::paste(&command) {
+                Ok(text) if !text.is_empty() => {
+                    return Some(Register {
+                        linewise: text.ends_with('\n'),
+                        text,
+                    });
+                }
+                Ok(_) => {}
+                Err(e) => buffer.set_error(&e),
+            }
+        }
+
+        self.registers.get(register, &buffer.file_name())
+    }
+
+    /// normal mode keys that are not motions or operators
+    fn normal_key(&mut self, buffer: &mut Buffer, key: char, count: usize, register: Option<char>) {
+        match key {
+            'q' => self.record_keys(buffer, register),
+            '@' => self.play(buffer, register, count),
+            'p' | 'P' => match self.register(buffer, register) {
+                Some(value) => buffer.put(&value.text, value.linewise, key == 'P', count),
+                None => {
+                    buffer.set_error(&format!("Nothing in register {}", register.unwrap_or('"')))
+                }
+            },
+            'i' => {
+                self.mode = EditorModes::Insert;
+                buffer.begin_change();
+                buffer.stdio.cursor_bar();
+            }
+            'u' => {
+                for _ in 0..count {
+                    buffer.undo();
+                }
+            }
+            ':' => {
+                self.mode = EditorModes::Command;
+                self.open_prompt(buffer, Prompt::new(PromptKind::Command, ":"));
+            }
+            '/' => self.open_search(buffer, Direction::Forward),
+            '?' => self.open_search(buffer, Direction::Backward),
+            'n' | 'N' => {
+                for _ in 0..count {
+                    self.repeat_search(buffer, key == 'N');
+                }
+            }
+            'v' => self.visual(buffer, EditorModes::Visual),
+            'V' => self.visual(buffer, EditorModes::VisualLine),
+            _ => {}
+        }
+    }
+
+    fn selection_kind(&self) -> Option<SelectionKind> {
+        match self.mode {
+            EditorModes::Visual => Some(SelectionKind::Char),
+            EditorModes::VisualLine => Some(SelectionKind::Line),
+            EditorModes::VisualBlock => Some(SelectionKind::Block),
+            _ => None,
+        }
+    }
+
+    /// enters visual mode or switches kind of the selection, the same mode exits it
+    fn visual(&mut self, buffer: &mut Buffer, mode: EditorModes) {
+        if std::mem::discriminant(&self.mode) == std::mem::discriminant(&mode) {
+            self.mode = EditorModes::Normal;
+            buffer.clear_selection();
+            return;
+        }
+
+        self.mode = mode;
+        if let Some(kind) = self.selection_kind() {
+            buffer.select(kind);
+        }
+    }
+
+    /// handles keys while selecting, operators act on the selection and return to normal mode
+    fn visual_input(&mut self, buffer: &mut Buffer, evt: Event) {
+        let motion = match evt {
+            Event::Key(Key::Left) => Some(Motions::Left),
+            Event::Key(Key::Right) => Some(Motions::Right),
+            Event::Key(Key::Up) => Some(Motions::Up),
+            Event::Key(Key::Down) => Some(Motions::Down),
+            _ => None,
+        };
+
+        if let Some(motion) = motion {
+            buffer.motion(motion);
+            buffer.redraw();
+            return;
+        }
+
+        // typed keys are motions and text objects, the rest act on the selection
+        let mut register = None;
+        let evt = match evt {
+            Event::Key(Key::Char(c)) => {
+                let parsed = self.keys.feed_visual(c);
+                buffer.set_pending(&self.keys.pending());
+
+                match parsed {
+                    Parsed::Done(Action::Move { motion, count }) => {
+                        let count = count.unwrap_or(1);
+                        if matches!(motion, Motions::Object { .. }) {
+                            if buffer.select_object(motion, count)
+                                && matches!(self.mode, EditorModes::Visual)
+                            {
+                                self.mode = EditorModes::VisualLine;
+                            }
+                        } else {
+                            self.failed |= !buffer.move_by(motion, count);
+                            buffer.redraw();
+                        }
+                        return;
                     }
-                    EditorModes::Normal => {
-                        self.mode = EditorModes::Insert;
-                        buffer.stdio.cursor_bar();
+                    Parsed::Done(Action::Key { key, .. }) => {
+                        register = self.keys.take_register();
+                        Event::Key(Key::Char(key))
                     }
+                    _ => return,
+                }
+            }
+            evt => {
+                self.keys.clear();
+                buffer.set_pending("");
+                evt
+            }
+        };
+
+        let linewise = matches!(self.mode, EditorModes::VisualLine);
+        match evt {
+            Event::Key(Key::Char('v')) => self.visual(buffer, EditorModes::Visual),
+            Event::Key(Key::Char('V')) => self.visual(buffer, EditorModes::VisualLine),
+            Event::Key(Key::Ctrl('v')) => self.visual(buffer, EditorModes::VisualBlock),
+            Event::Key(Key::Esc) | Event::Key(Key::Ctrl('c')) => {
+                self.mode = EditorModes::Normal;
+                buffer.clear_selection();
+            }
+            Event::Key(Key::Char('y' | 'd' | 'x' | 'c')) | Event::Key(Key::Delete)
+                if !writable(buffer, register) => {}
+            Event::Key(Key::Char('y')) => {
+                let text = buffer.selection_text();
+                self.store(buffer, register, &text, linewise, Operator::Yank);
+                self.mode = EditorModes::Normal;
+                buffer.clear_selection();
+            }
+            Event::Key(Key::Char('d')) | Event::Key(Key::Char('x')) | Event::Key(Key::Delete) => {
+                let text = buffer.selection_text();
+                self.store(buffer, register, &text, linewise, Operator::Delete);
+                self.mode = EditorModes::Normal;
+                buffer.delete_selection();
+            }
+            Event::Key(Key::Char('c')) => {
+                let text = buffer.selection_text();
+                self.store(buffer, register, &text, linewise, Operator::Change);
+                self.mode = EditorModes::Insert;
+                buffer.begin_change();
+                buffer.delete_selection();
+                buffer.stdio.cursor_bar();
+            }
+            Event::Key(Key::Char('>')) | Event::Key(Key::Char('<')) => {
+                self.mode = EditorModes::Normal;
+                buffer.shift_selection(evt == Event::Key(Key::Char('>')));
+            }
+            Event::Key(Key::Char('~')) => {
+                self.mode = EditorModes::Normal;
+                buffer.toggle_case_selection();
+            }
+            Event::Key(Key::Char(':')) => {
+                // the command works on the selected lines
+                self.mode = EditorModes::Command;
+                buffer.clear_selection();
+
+                let mut prompt = Prompt::new(PromptKind::Command, ":");
+                prompt.set_text("'<,'>");
+                self.open_prompt(buffer, prompt);
+            }
+            _ => {}
+        }
+    }
+
+    /// pasted text is typed into the prompt, inserted as its own undo step while typing, and
+    /// put before the cursor in normal mode
+    fn paste(&mut self, buffer: &mut Buffer, text: &str) {
+        if self.prompt.is_some() {
+            for ch in text.chars().filter(|c| *c != '\n') {
+                self.prompt_input(buffer, Event::Key(Key::Char(ch)));
+            }
+            return;
+        }
+
+        match self.mode {
+            EditorModes::Insert => {
+                self.record(Typed::Paste(text.to_string()));
+                buffer.end_change();
+                buffer.paste(text);
+                buffer.begin_change();
+            }
+            EditorModes::Normal if self.confirm.is_none() => buffer.put(text, false, true, 1),
+            _ => {}
+        }
+    }
+
+    /// moving the cursor while typing starts a new undo step, and `.` repeats only what is
+    /// typed after it
+    fn insert_motion(&mut self, buffer: &mut Buffer, motion: Motions) {
+        let typing = matches!(self.mode, EditorModes::Insert);
+        if typing {
+            self.stop_insert(buffer);
+            self.change = Some(Change {
+                action: Action::Key {
+                    key: 'i',
+                    count: None,
                 },
-                Event::Key(Key::Esc) => {
-                    self.mode = EditorModes::Normal;
-                    buffer.stdio.cursor_block();
+                register: None,
+                typed: Vec::new(),
+            });
+        }
+
+        buffer.motion(motion);
+
+        if typing {
+            buffer.begin_change();
+        }
+    }
+
+    fn open_prompt(&mut self, buffer: &mut Buffer, prompt: Prompt) {
+        buffer
+            .stdio
+            .display_prompt(&prompt.label, &prompt.text(), prompt.cursor_col());
+        self.prompt = Some(prompt);
+    }
+
+    fn open_search(&mut self, buffer: &mut Buffer, direction: Direction) {
+        let label = match direction {
+            Direction::Forward => "/",
+            Direction::Backward => "?",
+        };
+
+        self.search_origin = buffer.position();
+        self.open_prompt(buffer, Prompt::new(PromptKind::Search(direction), label));
+    }
+
+    /// jumps to the next match of the last pattern, reverse goes in the opposite direction
+    fn repeat_search(&mut self, buffer: &mut Buffer, reverse: bool) {
+        let (pattern, direction) = match self.search.clone() {
+            Some(s) => s,
+            None => {
+                buffer.set_error("No previous regular expression");
+                return;
+            }
+        };
+
+        let direction = if reverse {
+            direction.reverse()
+        } else {
+            direction
+        };
+
+        let pos = buffer.position();
+        find(buffer, &pattern, direction, pos);
+    }
+
+    /// handles keys while the prompt is open, returns true when the editor should quit
+    fn prompt_input(&mut self, buffer: &mut Buffer, evt: Event) -> bool {
+        let prompt = match self.prompt.as_mut() {
+            Some(p) => p,
+            None => return false,
+        };
+
+        let key = match evt {
+            Event::Key(k) => k,
+            _ => return false,
+        };
+
+        let history = match prompt.kind {
+            PromptKind::Search(_) => &self.search_history,
+            _ => &self.command_history,
+        };
+
+        match prompt.handle(key, history) {
+            PromptResult::Changed => {
+                // the cursor follows the first match while the pattern is typed
+                if let PromptKind::Search(direction) = prompt.kind {
+                    match Pattern::new(&prompt.text()) {
+                        Ok(pattern) => {
+                            buffer.search(&pattern, direction, self.search_origin);
+                        }
+                        Err(_) => {
+                            buffer.highlight = None;
+                            buffer.goto(self.search_origin.0, self.search_origin.1);
+                        }
+                    }
                 }
-                Event::Key(Key::Backspace) => {
-                    if matches!(self.mode, EditorModes::Normal) {
-                        buffer.motion(Motions::Left)
+                buffer
+                    .stdio
+                    .display_prompt(&prompt.label, &prompt.text(), prompt.cursor_col());
+                false
+            }
+            PromptResult::Cancel => {
+                if let PromptKind::Search(_) = prompt.kind {
+                    buffer.highlight = self.search.as_ref().and_then(|s| Pattern::new(&s.0).ok());
+                    buffer.stdio.set_status("");
+                    buffer.goto(self.search_origin.0, self.search_origin.1);
+                }
+                self.close_prompt();
+                buffer.set_status("");
+                false
+            }
+            PromptResult::Submit(input) => {
+                let kind = prompt.kind;
+                self.close_prompt();
+                buffer.set_status("");
+
+                match kind {
+                    PromptKind::SaveAs => {
+                        let path = input.trim();
+                        if path.is_empty() {
+                            buffer.set_error("No file name");
+                        } else {
+                            buffer.save_as(path);
+                        }
+                        false
+                    }
+                    PromptKind::Command => {
+                        remember(&mut self.command_history, &input, buffer.options.history);
+                        self.execute(buffer, &input)
                     }
+                    PromptKind::Search(direction) => {
+                        // empty pattern searches for the last one again
+                        let pattern = match (input.is_empty(), self.search.as_ref()) {
+                            (true, Some(last)) => last.0.clone(),
+                            _ => input,
+                        };
+                        if pattern.is_empty() {
+                            buffer.set_error("No previous regular expression");
+                            return false;
+                        }
+
+                        remember(&mut self.search_history, &pattern, buffer.options.history);
+                        self.search = Some((pattern.clone(), direction));
+                        find(buffer, &pattern, direction, self.search_origin);
+                        false
+                    }
+                }
+            }
+        }
+    }
+
+    fn close_prompt(&mut self) {
+        self.prompt = None;
+        if matches!(self.mode, EditorModes::Command) {
+            self.mode = EditorModes::Normal;
+        }
+    }
+
+    /// runs the command line, returns true when the editor should quit
+    fn execute(&mut self, buffer: &mut Buffer, input: &str) -> bool {
+        let cmd = match command::parse(input) {
+            Ok(c) => c,
+            Err(e) => {
+                if !e.is_empty() {
+                    buffer.set_error(&e);
+                }
+                return false;
+            }
+        };
 
-                    buffer.edit(codes::BACKSPACE);
-                }
-                Event::Key(Key::Char(ch)) => {
-                    if matches!(self.mode, EditorModes::Insert) {
-                        buffer.edit(ch);
-                    } else {
-                        match ch {
-                            'h' => buffer.motion(Motions::Left),
-                            'l' => buffer.motion(Motions::Right),
-                            'k' => buffer.motion(Motions::Up),
-                            'j' => buffer.motion(Motions::Down),
-                            _ => {}
+        let unsaved = "No write since last change (add ! to override)";
+        let exists = |path: &str, force: bool| {
+            !force && path != buffer.file_name() && std::path::Path::new(path).exists()
+        };
+
+        match cmd {
+            Command::Write { path, force } => match path {
+                Some(p) if exists(&p, force) => buffer.set_error("File exists (add ! to override)"),
+                Some(p) => {
+                    buffer.write_to(&p);
+                }
+                None => {
+                    buffer.save();
+                }
+            },
+            Command::Quit { force } => {
+                if !force && buffer.modified {
+                    buffer.set_error(unsaved);
+                    return false;
+                }
+                return true;
+            }
+            Command::Exit { .. } if !buffer.modified => return true,
+            Command::WriteQuit { path, force } | Command::Exit { path, force } => {
+                return match path {
+                    Some(p) if exists(&p, force) => {
+                        buffer.set_error("File exists (add ! to override)");
+                        false
+                    }
+                    Some(p) => buffer.write_to(&p),
+                    None => buffer.save(),
+                };
+            }
+            Command::Edit { path, force } => {
+                if !force && buffer.modified {
+                    buffer.set_error(unsaved);
+                    return false;
+                }
+
+                match path {
+                    Some(p) => buffer.open(&p),
+                    None if buffer.has_file_path() => buffer.open(&buffer.file_name()),
+                    None => buffer.set_error("No file name"),
+                }
+            }
+            Command::SaveAs { path, force } => {
+                if exists(&path, force) {
+                    buffer.set_error("File exists (add ! to override)");
+                } else {
+                    buffer.save_as(&path);
+                }
+            }
+            Command::Goto(ln) => buffer.goto(ln, 1),
+            Command::Registers => show_registers(buffer, &self.registers),
+            Command::NoHighlight => buffer.clear_highlight(),
+            Command::Substitute(cmd) => self.substitute(buffer, cmd),
+            Command::Set(args) => {
+                if args.is_empty() {
+                    let changed = buffer.options.show_changed();
+                    buffer.set_status(&changed);
+                    return false;
+                }
+
+                let mut shown = Vec::new();
+                for arg in args.iter() {
+                    match buffer.options.set(arg) {
+                        Ok(Some(msg)) => shown.push(msg),
+                        Ok(None) => {}
+                        Err(e) => {
+                            buffer.set_error(&e);
+                            return false;
                         }
                     }
                 }
-                _ => {}
-            };
-            buffer.stdio.stdout.flush().unwrap();
+                // tab stops and wrapping may have changed, so lines are loaded and drawn again
+                buffer.stdio.set_status(&shown.join("  "));
+                buffer.relayout();
+            }
+        }
+
+        false
+    }
+
+    fn substitute(&mut self, buffer: &mut Buffer, cmd: Substitute) {
+        let current = buffer.position().0;
+        let count = buffer.data.line_count();
+        let marks = buffer.visual_marks;
+        let resolve = |address: Address| match address {
+            Address::Line(ln) => Ok(ln),
+            Address::Current => Ok(current),
+            Address::Last => Ok(count),
+            Address::Mark(mark) => match marks {
+                Some(m) if mark == '<' => Ok(m.0),
+                Some(m) => Ok(m.1),
+                None => Err(String::from("Mark not set")),
+            },
+        };
+
+        let lines = match cmd.range {
+            Some(range) => resolve(range.from).and_then(|f| Ok((f, resolve(range.to)?))),
+            None => Ok((current, current)),
+        };
+        let lines = match lines {
+            Ok((from, to)) if from.min(to) >= 1 && from.max(to) <= count => {
+                (from.min(to), from.max(to))
+            }
+            Ok(_) => {
+                buffer.set_error("Invalid range");
+                return;
+            }
+            Err(e) => {
+                buffer.set_error(&e);
+                return;
+            }
+        };
+
+        // empty pattern uses the last searched one
+        let pattern = match (cmd.pattern.is_empty(), self.search.as_ref()) {
+            (false, _) => cmd.pattern.clone(),
+            (true, Some(last)) => last.0.clone(),
+            (true, None) => {
+                buffer.set_error("No previous regular expression");
+                return;
+            }
+        };
+        let regex = match search::compile(&pattern, cmd.ignore_case) {
+            Ok(r) => r,
+            Err(e) => {
+                buffer.set_error(&e);
+                return;
+            }
+        };
+        self.search = Some((pattern.clone(), Direction::Forward));
+
+        let replacements =
+            search::substitutions(&buffer.data, &regex, &cmd.replacement, lines, cmd.global);
+        if replacements.is_empty() {
+            buffer.set_error(&format!("Pattern not found: {}", pattern));
+            return;
+        }
+
+        if cmd.confirm {
+            buffer.highlight = Some(Pattern::Regex(regex));
+            buffer.begin_change();
+            self.confirm = Some(Confirm {
+                replacements,
+                next: 0,
+                shift: 0,
+                accepted: Vec::new(),
+            });
+            self.ask_confirm(buffer);
+            return;
         }
+
+        buffer.replace(&replacements);
+        report(buffer, &replacements);
+    }
+
+    /// moves to the next match and asks whether to replace it
+    fn ask_confirm(&mut self, buffer: &mut Buffer) {
+        let confirm = match self.confirm.as_ref() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let r = &confirm.replacements[confirm.next];
+        buffer.goto_offset((r.offset as isize + confirm.shift) as usize);
+
+        let label = format!("replace with {} (y/n/a/q/l)?", r.text.replace('\n', "^M"));
+        let col = label.width() as u16 + 1;
+        buffer.stdio.display_prompt(&label, "", col);
+    }
+
+    /// y replaces the match, n skips it, a replaces all remaining, l replaces this one and
+    /// stops, q or Esc stops. all replacements are undone as one step.
+    fn confirm_input(&mut self, buffer: &mut Buffer, evt: Event) {
+        let confirm = match self.confirm.as_mut() {
+            Some(c) => c,
+            None => return,
+        };
+
+        let shifted = |c: &Confirm, r: &Replacement| Replacement {
+            offset: (r.offset as isize + c.shift) as usize,
+            ..r.clone()
+        };
+
+        let (take, stop) = match evt {
+            Event::Key(Key::Char('y')) => (1, false),
+            Event::Key(Key::Char('l')) => (1, true),
+            Event::Key(Key::Char('n')) => (0, false),
+            Event::Key(Key::Char('a')) => (confirm.replacements.len() - confirm.next, true),
+            Event::Key(Key::Char('q')) | Event::Key(Key::Esc) | Event::Key(Key::Ctrl('c')) => {
+                (0, true)
+            }
+            _ => return,
+        };
+
+        if take > 0 {
+            let taken = &confirm.replacements[confirm.next..confirm.next + take];
+            let shifted: Vec<Replacement> = taken.iter().map(|r| shifted(confirm, r)).collect();
+            buffer.replace(&shifted);
+
+            for r in taken {
+                confirm.shift += r.text.len() as isize - r.len as isize;
+                confirm.accepted.push(r.clone());
+            }
+        }
+        confirm.next += take.max(1);
+
+        if stop || confirm.next >= confirm.replacements.len() {
+            let accepted = std::mem::take(&mut confirm.accepted);
+            self.confirm = None;
+            buffer.end_change();
+            if accepted.is_empty() {
+                buffer.set_status("");
+            } else {
+                report(buffer, &accepted);
+            }
+            return;
+        }
+
+        self.ask_confirm(buffer);
+    }
+}
+
+/// moves to the match of the pattern from the position, or reports why there is none
+fn find(buffer: &mut Buffer, pattern: &str, direction: Direction, from: (usize, usize)) {
+    match Pattern::new(pattern) {
+        Ok(p) => {
+            if !buffer.search(&p, direction, from) {
+                buffer.set_error(&format!("Pattern not found: {}", pattern));
+            }
+        }
+        Err(e) => {
+            buffer.highlight = None;
+            buffer.goto(from.0, from.1);
+            buffer.set_error(&e);
+        }
+    }
+}
+
+/// shows count of replacements and lines they were made in
+fn report(buffer: &mut Buffer, replacements: &[Replacement]) {
+    let mut lines: Vec<usize> = replacements.iter().map(|r| r.line).collect();
+    lines.dedup();
+
+    let count = |n: usize, what: &str| match n {
+        1 => format!("1 {}", what),
+        _ => format!("{} {}s", n, what),
+    };
+    buffer.set_status(&format!(
+        "{} on {}",
+        count(replacements.len(), "substitution"),
+        count(lines.len(), "line")
+    ));
+}
+
+/// commands that change the text, `.` repeats the last of them
+fn is_change(action: &Action) -> bool {
+    match action {
+        Action::Operate { op, .. } => *op != Operator::Yank,
+        Action::Key { key, .. } => matches!(key, 'i' | 'p' | 'P'),
+        Action::Move { .. } => false,
+    }
+}
+
+/// shows an error when text can't be stored in the register
+fn writable(buffer: &mut Buffer, register: Option<char>) -> bool {
+    let ok = Registers::is_writable(register);
+    if !ok {
+        buffer.set_error(&format!(
+            "Invalid register name: {}",
+            register.unwrap_or('"')
+        ));
+    }
+    ok
+}
+
+/// lists registers like `"a text`, line breaks and tabs are shown as ^J and ^I
+fn show_registers(buffer: &mut Buffer, registers: &Registers) {
+    let list = registers
+        .list(&buffer.file_name())
+        .iter()
+        .map(|(name, value)| {
+            let text = value.text.replace('\n', "^J").replace('\t', "^I");
+            format!("\"{} {}", name, text)
+        })
+        .collect::<Vec<String>>()
+        .join("  ");
+
+    buffer.set_status(&list);
+}
+
+/// adds the entry as the newest one, older copies of it are dropped
+fn remember(history: &mut Vec<String>, input: &str, limit: usize) {
+    if input.trim().is_empty() {
+        return;
+    }
+
+    history.retain(|c| c != input);
+    history.push(input.to_string());
+    if history.len() > limit {
+        let extra = history.len() - limit;
+        history.drain(..extra);
     }
 }
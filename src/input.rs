@@ -0,0 +1,61 @@
+use signal_hook::consts::SIGWINCH;
+use signal_hook::iterator::Signals;
+use std::io::Stdin;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use termion::event::Event;
+use termion::input::TermRead;
+
+/// what the editor reacts to, events from the terminal and changes of it's size
+#[derive(Clone, Debug, PartialEq)]
+pub enum Input {
+    Event(Event),
+    /// new (width, height) of the terminal
+    Resize((u16, u16)),
+    /// stdin ended, nothing more can be typed
+    Closed,
+}
+
+/// reads events from stdin and resize signals on their own threads, both are sent into the
+/// returned channel in the order they come. Closed is sent last when stdin ends.
+pub fn listen(stdin: Stdin) -> Receiver<Input> {
+    let (sender, receiver) = mpsc::channel();
+
+    let events = sender.clone();
+    thread::spawn(move || {
+        for evt in stdin.events() {
+            let evt = match evt {
+                Ok(e) => e,
+                Err(_) => break,
+            };
+            if events.send(Input::Event(evt)).is_err() {
+                return;
+            }
+        }
+        // the resize thread keeps the channel open, so the end is sent
+        let _ = events.send(Input::Closed);
+    });
+
+    listen_resize(sender);
+    receiver
+}
+
+/// SIGWINCH is sent when the terminal is resized, the size is read again each time
+fn listen_resize(sender: Sender<Input>) {
+    let mut signals = match Signals::new([SIGWINCH]) {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    thread::spawn(move || {
+        for _ in signals.forever() {
+            let size = match termion::terminal_size() {
+                Ok(s) => s,
+                Err(_) => continue,
+            };
+            if sender.send(Input::Resize(size)).is_err() {
+                break;
+            }
+        }
+    });
+}
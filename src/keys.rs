@@ -0,0 +1,73 @@
+use crate::codes;
+use termion::event::{Event, Key};
+
+/// keys without a char of their own are kept as chars from the private use area
+const SPECIAL: &[(Key, char)] = &[
+    (Key::Left, '\u{E000}'),
+    (Key::Right, '\u{E001}'),
+    (Key::Up, '\u{E002}'),
+    (Key::Down, '\u{E003}'),
+    (Key::Home, '\u{E004}'),
+    (Key::End, '\u{E005}'),
+    (Key::PageUp, '\u{E006}'),
+    (Key::PageDown, '\u{E007}'),
+    (Key::Delete, '\u{E008}'),
+    (Key::Insert, '\u{E009}'),
+    (Key::BackTab, '\u{E00A}'),
+];
+
+/// around text pasted while recording, so playing it pastes the text again
+pub const PASTE_START: char = '\u{E00B}';
+pub const PASTE_END: char = '\u{E00C}';
+
+/// keys as text that can be kept in a register, put into the buffer, edited and yanked
+/// back. keys that have no text are dropped.
+pub fn encode(keys: &[Key]) -> String {
+    keys.iter().filter_map(|key| encode_key(*key)).collect()
+}
+
+fn encode_key(key: Key) -> Option<char> {
+    if let Some((_, ch)) = SPECIAL.iter().find(|(k, _)| *k == key) {
+        return Some(*ch);
+    }
+
+    match key {
+        Key::Char(ch) => Some(ch),
+        Key::Esc => Some('\x1b'),
+        Key::Backspace => Some('\x7f'),
+        Key::Ctrl(ch @ 'a'..='z') => Some(char::from(ch as u8 - b'a' + 1)),
+        Key::Ctrl(ch @ '4'..='7') => Some(char::from(ch as u8 - b'4' + 0x1c)),
+        _ => None,
+    }
+}
+
+/// keys the text was made from, line breaks and tabs are typed as they are, like the
+/// terminal sends them
+pub fn decode(text: &str) -> Vec<Key> {
+    text.chars().map(decode_char).collect()
+}
+
+fn decode_char(ch: char) -> Key {
+    if let Some((key, _)) = SPECIAL.iter().find(|(_, c)| *c == ch) {
+        return *key;
+    }
+
+    match ch {
+        '\n' | '\r' => Key::Char('\n'),
+        '\t' => Key::Char('\t'),
+        '\x1b' => Key::Esc,
+        '\x7f' => Key::Backspace,
+        '\x01'..='\x1a' => Key::Ctrl(char::from(ch as u8 - 1 + b'a')),
+        '\x1c'..='\x1f' => Key::Ctrl(char::from(ch as u8 - 0x1c + b'4')),
+        ch => Key::Char(ch),
+    }
+}
+
+/// event a played key is handled as
+pub fn event(key: Key) -> Event {
+    match key {
+        Key::Char(PASTE_START) => Event::Unsupported(codes::PASTE_START.to_vec()),
+        Key::Char(PASTE_END) => Event::Unsupported(codes::PASTE_END.to_vec()),
+        key => Event::Key(key),
+    }
+}
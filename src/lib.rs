@@ -0,0 +1,18 @@
+pub mod backend;
+pub mod clipboard;
+pub mod codes;
+pub mod command;
+pub mod editor;
+pub mod input;
+pub mod keys;
+pub mod logger;
+pub mod motion;
+pub mod normal;
+pub mod options;
+pub mod prompt;
+pub mod register;
+pub mod status;
+pub mod stdio;
+pub mod text_object;
+pub mod utils;
+pub mod window;
@@ -13,7 +13,6 @@ pub fn log_to_file(msg: &str) {
     };
 
     let mut file = match OpenOptions::new()
-        .write(true)
         .append(true)
         .create(true)
         .open("logs.txt")
@@ -1,17 +1,11 @@
 use std::env::args;
 
-mod codes;
-mod constants;
-mod editor;
-mod logger;
-mod motion;
-mod stdio;
-mod utils;
-mod window;
+use text_editor::editor;
+use text_editor::input;
 
 fn main() {
     let path_arg = args().nth(1);
-    let stdin = std::io::stdin();
+    let input = input::listen(std::io::stdin());
     let mut editor = editor::Editor::new();
-    editor.run(path_arg, stdin);
+    editor.run(path_arg, input);
 }
@@ -1,6 +1,491 @@
+use crate::text_object::TextObject;
+use crate::window::piece_table::PieceTable;
+use crate::window::segment::SegmentNode;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Motions {
     Up,
     Down,
     Left,
     Right,
+    /// `w`, start of the next word
+    WordForward,
+    /// `b`, start of the word before the cursor
+    WordBackward,
+    /// `e`, end of the word
+    WordEnd,
+    /// `W`, `B` and `E` take any items except white space as a word
+    BigWordForward,
+    BigWordBackward,
+    BigWordEnd,
+    /// `0`
+    LineStart,
+    /// `^`, first item that is not white space
+    FirstNonBlank,
+    /// `$`, last item of the line
+    LineEnd,
+    /// `gg` or `[count]G`, the count is the line number
+    GotoLine,
+    /// `G`
+    LastLine,
+    /// `}` and `{`, empty line after or before the paragraph
+    ParagraphForward,
+    ParagraphBackward,
+    /// `%`, bracket that pairs with the one under or after the cursor
+    MatchPair,
+    /// `f`, `F`, `t` and `T`, till stops one item before the char
+    FindChar {
+        ch: char,
+        forward: bool,
+        till: bool,
+    },
+    /// `;` and `,` repeat the last FindChar, the parser replaces it with the find itself
+    RepeatFind {
+        reverse: bool,
+    },
+    /// `H`, `M` and `L`, lines of the screen, the buffer resolves them into GotoLine
+    ScreenTop,
+    ScreenMiddle,
+    ScreenBottom,
+    /// `gj` and `gk`, rows of the screen a wrapped line is shown on, the buffer resolves them
+    RowDown,
+    RowUp,
+    /// current line and count - 1 lines below, used by doubled operators like `dd`
+    Lines,
+    /// `iw`, `a(` and others after an operator or in visual mode, the buffer resolves the
+    /// range around the cursor
+    Object {
+        object: TextObject,
+        inner: bool,
+    },
+}
+
+/// how an operator takes the text between the cursor and where the motion ends
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MotionKind {
+    /// item at the end is left out
+    Exclusive,
+    /// item at the end is included
+    Inclusive,
+    /// whole lines are taken
+    Linewise,
+}
+
+impl Motions {
+    pub fn kind(&self) -> MotionKind {
+        match self {
+            Motions::Up
+            | Motions::Down
+            | Motions::Lines
+            | Motions::GotoLine
+            | Motions::LastLine
+            | Motions::ScreenTop
+            | Motions::ScreenMiddle
+            | Motions::ScreenBottom => MotionKind::Linewise,
+            Motions::LineEnd | Motions::WordEnd | Motions::BigWordEnd | Motions::MatchPair => {
+                MotionKind::Inclusive
+            }
+            Motions::FindChar { forward: true, .. } => MotionKind::Inclusive,
+            _ => MotionKind::Exclusive,
+        }
+    }
+
+    /// the same motion in the opposite direction, used by `,`
+    pub fn reverse(&self) -> Motions {
+        match *self {
+            Motions::FindChar { ch, forward, till } => Motions::FindChar {
+                ch,
+                forward: !forward,
+                till,
+            },
+            other => other,
+        }
+    }
+
+    /// document offset where the motion repeated count times from the offset ends, None when
+    /// it can't move. linewise motions end at the start of the line they reach, jumps to
+    /// a line end at it's first item that is not white space.
+    pub fn target(&self, data: &PieceTable, offset: usize, count: usize) -> Option<usize> {
+        let count = count.max(1);
+        let ln = data.line_at(offset);
+        let last_ln = data.line_count();
+        let mut walker = Walker::new(data, offset);
+
+        match *self {
+            Motions::Left => {
+                let start = data.line_offset(ln)?;
+                for _ in 0..count {
+                    if walker.offset <= start || !walker.back() {
+                        break;
+                    }
+                }
+            }
+            Motions::Right => {
+                for _ in 0..count {
+                    if matches!(walker.char(), None | Some('\n')) {
+                        break;
+                    }
+                    walker.forward();
+                }
+            }
+            Motions::Up => return data.line_offset(ln.checked_sub(count).filter(|l| *l >= 1)?),
+            Motions::Down => return data.line_offset(Some(ln + count).filter(|l| *l <= last_ln)?),
+            Motions::Lines => return data.line_offset((ln + count - 1).min(last_ln)),
+            Motions::WordForward | Motions::BigWordForward => {
+                let big = *self == Motions::BigWordForward;
+                for _ in 0..count {
+                    word_forward(&mut walker, big);
+                }
+            }
+            Motions::WordBackward | Motions::BigWordBackward => {
+                let big = *self == Motions::BigWordBackward;
+                for _ in 0..count {
+                    word_backward(&mut walker, big);
+                }
+            }
+            Motions::WordEnd | Motions::BigWordEnd => {
+                let big = *self == Motions::BigWordEnd;
+                for _ in 0..count {
+                    word_end(&mut walker, big);
+                }
+            }
+            Motions::LineStart => return data.line_offset(ln),
+            Motions::FirstNonBlank => return Some(first_non_blank(data, ln)),
+            Motions::LineEnd => {
+                let target_ln = (ln + count - 1).min(last_ln);
+                let start = data.line_offset(target_ln)?;
+                let end = line_end(data, target_ln);
+                walker = Walker::new(data, end);
+                if end > start {
+                    walker.back();
+                }
+            }
+            Motions::GotoLine
+            | Motions::ScreenTop
+            | Motions::ScreenMiddle
+            | Motions::ScreenBottom => {
+                return Some(first_non_blank(data, count.min(last_ln)));
+            }
+            Motions::LastLine => return Some(first_non_blank(data, last_ln)),
+            Motions::ParagraphForward => {
+                let mut l = ln;
+                for _ in 0..count {
+                    while l < last_ln && is_empty_line(data, l) {
+                        l += 1;
+                    }
+                    while l < last_ln && !is_empty_line(data, l) {
+                        l += 1;
+                    }
+                }
+
+                // the last paragraph ends at the last item of the document
+                if !is_empty_line(data, l) {
+                    walker = Walker::new(data, line_end(data, l));
+                    walker.back();
+                } else {
+                    walker = Walker::new(data, data.line_offset(l)?);
+                }
+            }
+            Motions::ParagraphBackward => {
+                let mut l = ln;
+                for _ in 0..count {
+                    while l > 1 && is_empty_line(data, l) {
+                        l -= 1;
+                    }
+                    while l > 1 && !is_empty_line(data, l) {
+                        l -= 1;
+                    }
+                }
+                walker = Walker::new(data, data.line_offset(l)?);
+            }
+            Motions::MatchPair => return match_pair(data, offset),
+            Motions::FindChar { ch, forward, till } => {
+                for i in 0..count {
+                    // repeating till starts after the char it stopped before
+                    if till && i > 0 {
+                        step(&mut walker, forward);
+                    }
+                    if !find_char(&mut walker, ch, forward) {
+                        return None;
+                    }
+                }
+                if till {
+                    step(&mut walker, !forward);
+                }
+            }
+            Motions::RepeatFind { .. }
+            | Motions::Object { .. }
+            | Motions::RowDown
+            | Motions::RowUp => return None,
+        }
+
+        Some(walker.offset).filter(|o| *o != offset)
+    }
+}
+
+fn step(walker: &mut Walker, forward: bool) -> bool {
+    if forward {
+        walker.forward()
+    } else {
+        walker.back()
+    }
+}
+
+/// moves to the next (or previous) ch within the line
+fn find_char(walker: &mut Walker, ch: char, forward: bool) -> bool {
+    let start = walker.offset;
+
+    loop {
+        if !step(walker, forward) || walker.char().is_none_or(|c| c == '\n') {
+            walker.offset = start;
+            return false;
+        }
+        if walker.char() == Some(ch) {
+            return true;
+        }
+    }
+}
+
+fn is_empty_line(data: &PieceTable, ln: usize) -> bool {
+    match data.line_offset(ln) {
+        Some(start) => line_end(data, ln) == start,
+        None => true,
+    }
+}
+
+/// offset of the first item in the line that is not white space
+pub fn first_non_blank(data: &PieceTable, ln: usize) -> usize {
+    let mut walker = Walker::new(data, data.line_offset(ln).unwrap_or(0));
+    while matches!(walker.char(), Some(' ') | Some('\t')) {
+        walker.forward();
+    }
+    walker.offset
+}
+
+/// bracket under or after the cursor in the line, and the one that pairs with it
+fn match_pair(data: &PieceTable, offset: usize) -> Option<usize> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+
+    let mut walker = Walker::new(data, offset);
+    let (open, close, forward) = loop {
+        let c = walker.char().filter(|c| *c != '\n')?;
+        if let Some(p) = PAIRS.iter().find(|p| p.0 == c) {
+            break (p.0, p.1, true);
+        }
+        if let Some(p) = PAIRS.iter().find(|p| p.1 == c) {
+            break (p.1, p.0, false);
+        }
+        walker.forward();
+    };
+
+    let mut depth = 0;
+    loop {
+        match walker.char() {
+            Some(c) if c == open => depth += 1,
+            Some(c) if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(walker.offset);
+                }
+            }
+            _ => {}
+        }
+        if !step(&mut walker, forward) {
+            return None;
+        }
+    }
+}
+
+/// offset of the line break that ends the line, or the document length for the last line
+pub fn line_end(data: &PieceTable, ln: usize) -> usize {
+    match data.line_offset(ln + 1) {
+        Some(next) => next - 1,
+        None => data.len(),
+    }
+}
+
+#[derive(PartialEq)]
+enum CharClass {
+    Space,
+    Word,
+    Punctuation,
+}
+
+/// big words are made of any items except white space
+fn class(c: char, big: bool) -> CharClass {
+    if c.is_whitespace() {
+        CharClass::Space
+    } else if big || c.is_alphanumeric() || c == '_' {
+        CharClass::Word
+    } else {
+        CharClass::Punctuation
+    }
+}
+
+/// line break at the start of a line, it's an empty line that counts as a word
+fn at_empty_line(walker: &mut Walker) -> bool {
+    if walker.char() != Some('\n') {
+        return false;
+    }
+    if walker.offset == 0 {
+        return true;
+    }
+
+    let offset = walker.offset;
+    walker.back();
+    let empty = walker.char() == Some('\n');
+    walker.offset = offset;
+    empty
+}
+
+/// skips the rest of the word and following white space, an empty line counts as a word
+fn word_forward(walker: &mut Walker, big: bool) {
+    let start = match walker.char() {
+        Some(c) => class(c, big),
+        None => return,
+    };
+
+    if start != CharClass::Space {
+        while walker.char().is_some_and(|c| class(c, big) == start) {
+            walker.forward();
+        }
+    }
+
+    loop {
+        match walker.char() {
+            Some('\n') => {
+                walker.forward();
+                if walker.char() == Some('\n') {
+                    break;
+                }
+            }
+            Some(c) if c.is_whitespace() => {
+                walker.forward();
+            }
+            _ => break,
+        }
+    }
+}
+
+/// moves to the start of the word before the cursor, or of the one it's within
+fn word_backward(walker: &mut Walker, big: bool) {
+    if !walker.back() {
+        return;
+    }
+
+    while walker.char().is_some_and(|c| c.is_whitespace()) && !at_empty_line(walker) {
+        if !walker.back() {
+            return;
+        }
+    }
+    if at_empty_line(walker) {
+        return;
+    }
+
+    let word = walker.char().map(|c| class(c, big));
+    loop {
+        let offset = walker.offset;
+        if !walker.back() {
+            return;
+        }
+        if walker.char().map(|c| class(c, big)) != word {
+            walker.offset = offset;
+            return;
+        }
+    }
+}
+
+/// moves to the last item of the word, or of the next one when it's already there
+fn word_end(walker: &mut Walker, big: bool) {
+    if !walker.forward() {
+        return;
+    }
+
+    while walker.char().is_some_and(|c| c.is_whitespace()) {
+        if !walker.forward() {
+            return;
+        }
+    }
+
+    let word = walker.char().map(|c| class(c, big));
+    loop {
+        let offset = walker.offset;
+        if !walker.forward() || walker.char().map(|c| class(c, big)) != word {
+            walker.offset = offset;
+            return;
+        }
+    }
+}
+
+/// walks the document one char at a time in both directions, the line under the walker is
+/// loaded from the piece table when the walk enters it
+pub struct Walker<'a> {
+    data: &'a PieceTable,
+    line: Option<SegmentNode>,
+    pub offset: usize,
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(data: &'a PieceTable, offset: usize) -> Walker<'a> {
+        Walker {
+            data,
+            line: None,
+            offset: offset.min(data.len()),
+        }
+    }
+
+    fn line_with(&mut self, offset: usize) -> Option<&SegmentNode> {
+        let loaded = self
+            .line
+            .as_ref()
+            .is_some_and(|l| l.offset <= offset && offset < l.offset + l.value.len());
+
+        if !loaded {
+            let ln = self.data.line_at(offset);
+            self.line = self.data.get_lines(ln, ln).front().cloned();
+        }
+
+        self.line.as_ref()
+    }
+
+    /// item under the walker, None at the end of the document
+    pub fn char(&mut self) -> Option<char> {
+        let offset = self.offset;
+        if offset >= self.data.len() {
+            return None;
+        }
+
+        let line = self.line_with(offset)?;
+        line.value[offset - line.offset..].chars().next()
+    }
+
+    pub fn forward(&mut self) -> bool {
+        match self.char() {
+            Some(c) => {
+                self.offset += c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn back(&mut self) -> bool {
+        if self.offset == 0 {
+            return false;
+        }
+
+        let offset = self.offset;
+        let c = match self.line_with(offset - 1) {
+            Some(line) => line.value[..offset - line.offset].chars().next_back(),
+            None => None,
+        };
+
+        match c {
+            Some(c) => {
+                self.offset -= c.len_utf8();
+                true
+            }
+            None => false,
+        }
+    }
 }
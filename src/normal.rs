@@ -0,0 +1,383 @@
+use crate::motion::Motions;
+use crate::register::Registers;
+use crate::text_object::TextObject;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Delete,
+    Change,
+    Yank,
+    ShiftRight,
+    ShiftLeft,
+}
+
+/// what the keys typed in normal mode ask for, counts are None when not typed
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    /// moves the cursor, e.g. `3w`
+    Move {
+        motion: Motions,
+        count: Option<usize>,
+    },
+    /// operator acts on the text the motion moves over, e.g. `d3w` or `dd`
+    Operate {
+        op: Operator,
+        motion: Motions,
+        count: Option<usize>,
+    },
+    /// any other key, e.g. `i` or `u`
+    Key { key: char, count: Option<usize> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Parsed {
+    /// more keys are needed
+    Pending,
+    Done(Action),
+    /// keys don't form a command, they are dropped
+    Invalid,
+}
+
+/// collects keys typed in normal mode until they form `["x][count][operator][count][motion]`
+#[derive(Debug, Default)]
+pub struct Parser {
+    keys: String,
+    /// register typed with `"x`, the editor takes it when the command is done
+    register: Option<char>,
+    /// last `f`, `F`, `t` or `T`, repeated by `;` and `,`
+    last_find: Option<Motions>,
+    /// keys are being recorded into a register, `q` alone stops it
+    pub recording: bool,
+}
+
+impl Parser {
+    pub fn new() -> Parser {
+        Parser {
+            keys: String::new(),
+            register: None,
+            last_find: None,
+            recording: false,
+        }
+    }
+
+    /// keys typed so far, shown on the status line
+    pub fn pending(&self) -> String {
+        match self.register {
+            Some(name) => format!("\"{}{}", name, self.keys),
+            None => self.keys.clone(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.register = None;
+    }
+
+    /// register typed for the command that is done
+    pub fn take_register(&mut self) -> Option<char> {
+        self.register.take()
+    }
+
+    pub fn feed(&mut self, key: char) -> Parsed {
+        self.feed_with(key, parse)
+    }
+
+    /// keys typed in visual mode, where text objects select text
+    pub fn feed_visual(&mut self, key: char) -> Parsed {
+        self.feed_with(key, parse_visual)
+    }
+
+    fn feed_with(&mut self, key: char, parse: fn(&str) -> Parsed) -> Parsed {
+        self.keys.push(key);
+
+        // `"x` can come before or after a count
+        let (_, rest) = take_count(&self.keys);
+        if let Some(name) = rest.strip_prefix('"') {
+            return match name.chars().next() {
+                Some(name) if Registers::is_valid(name) => {
+                    self.register = Some(name);
+                    self.keys.truncate(self.keys.len() - rest.len());
+                    Parsed::Pending
+                }
+                Some(_) => {
+                    self.clear();
+                    Parsed::Invalid
+                }
+                None => Parsed::Pending,
+            };
+        }
+
+        // `q{reg}` and `@{reg}` name the register after the key
+        let (count, rest) = take_count(&self.keys);
+        let mut chars = rest.chars();
+        if let Some(key @ ('q' | '@')) = chars.next() {
+            let name = chars.next();
+            let parsed = match name {
+                None if key == 'q' && self.recording => Parsed::Done(Action::Key { key, count }),
+                None => return Parsed::Pending,
+                Some('@') if key == '@' => Parsed::Done(Action::Key { key, count }),
+                Some(name) if Registers::is_valid(name) => Parsed::Done(Action::Key { key, count }),
+                Some(_) => Parsed::Invalid,
+            };
+
+            self.clear();
+            if parsed != Parsed::Invalid {
+                self.register = name;
+            }
+            return parsed;
+        }
+
+        let mut parsed = parse(&self.keys);
+        match parsed {
+            Parsed::Pending => {}
+            Parsed::Invalid => self.clear(),
+            Parsed::Done(_) => self.keys.clear(),
+        }
+
+        let motion = match &mut parsed {
+            Parsed::Done(Action::Move { motion, .. }) => motion,
+            Parsed::Done(Action::Operate { motion, .. }) => motion,
+            _ => return parsed,
+        };
+
+        match *motion {
+            Motions::FindChar { .. } => self.last_find = Some(*motion),
+            Motions::RepeatFind { reverse } => match self.last_find {
+                Some(find) if reverse => *motion = find.reverse(),
+                Some(find) => *motion = find,
+                None => return Parsed::Invalid,
+            },
+            _ => {}
+        }
+
+        parsed
+    }
+}
+
+/// keys that are short for an operator with a motion
+fn shorthand(key: char) -> Option<&'static str> {
+    match key {
+        'x' => Some("dl"),
+        'X' => Some("dh"),
+        'D' => Some("d$"),
+        'C' => Some("c$"),
+        's' => Some("cl"),
+        'S' => Some("cc"),
+        'Y' => Some("yy"),
+        _ => None,
+    }
+}
+
+fn operator(key: char) -> Option<Operator> {
+    match key {
+        'd' => Some(Operator::Delete),
+        'c' => Some(Operator::Change),
+        'y' => Some(Operator::Yank),
+        '>' => Some(Operator::ShiftRight),
+        '<' => Some(Operator::ShiftLeft),
+        _ => None,
+    }
+}
+
+enum MotionParse {
+    Pending,
+    Done(Motions),
+    Invalid,
+}
+
+/// motion made of all the keys
+fn motion(keys: &str) -> MotionParse {
+    let mut chars = keys.chars();
+    let m = match chars.next() {
+        Some('h') => Motions::Left,
+        Some('l') | Some(' ') => Motions::Right,
+        Some('k') => Motions::Up,
+        Some('j') => Motions::Down,
+        Some('w') => Motions::WordForward,
+        Some('b') => Motions::WordBackward,
+        Some('e') => Motions::WordEnd,
+        Some('W') => Motions::BigWordForward,
+        Some('B') => Motions::BigWordBackward,
+        Some('E') => Motions::BigWordEnd,
+        Some('0') => Motions::LineStart,
+        Some('^') => Motions::FirstNonBlank,
+        Some('$') => Motions::LineEnd,
+        Some('G') => Motions::LastLine,
+        Some('}') => Motions::ParagraphForward,
+        Some('{') => Motions::ParagraphBackward,
+        Some('%') => Motions::MatchPair,
+        Some(';') => Motions::RepeatFind { reverse: false },
+        Some(',') => Motions::RepeatFind { reverse: true },
+        Some('H') => Motions::ScreenTop,
+        Some('M') => Motions::ScreenMiddle,
+        Some('L') => Motions::ScreenBottom,
+        Some('g') => match chars.next() {
+            Some('g') => Motions::GotoLine,
+            Some('j') => Motions::RowDown,
+            Some('k') => Motions::RowUp,
+            Some(_) => return MotionParse::Invalid,
+            None => return MotionParse::Pending,
+        },
+        Some(key @ ('f' | 'F' | 't' | 'T')) => match chars.next() {
+            Some(ch) => Motions::FindChar {
+                ch,
+                forward: key == 'f' || key == 't',
+                till: key == 't' || key == 'T',
+            },
+            None => return MotionParse::Pending,
+        },
+        Some(_) => return MotionParse::Invalid,
+        None => return MotionParse::Pending,
+    };
+
+    match chars.next() {
+        None => MotionParse::Done(m),
+        Some(_) => MotionParse::Invalid,
+    }
+}
+
+/// text object made of all the keys, `i` or `a` and the object
+fn object(keys: &str) -> MotionParse {
+    let mut chars = keys.chars();
+    let inner = match chars.next() {
+        Some('i') => true,
+        Some('a') => false,
+        Some(_) => return MotionParse::Invalid,
+        None => return MotionParse::Pending,
+    };
+
+    let object = match chars.next() {
+        Some(key) => match TextObject::from_key(key) {
+            Some(object) => object,
+            None => return MotionParse::Invalid,
+        },
+        None => return MotionParse::Pending,
+    };
+
+    match chars.next() {
+        None => MotionParse::Done(Motions::Object { object, inner }),
+        Some(_) => MotionParse::Invalid,
+    }
+}
+
+/// `G` with a count goes to the line, like `gg`
+fn with_count(motion: Motions, count: Option<usize>) -> Motions {
+    match (motion, count) {
+        (Motions::LastLine, Some(_)) => Motions::GotoLine,
+        _ => motion,
+    }
+}
+
+/// leading count, 0 can't start it as it's a motion
+fn take_count(keys: &str) -> (Option<usize>, &str) {
+    if !keys.starts_with(|c: char| ('1'..='9').contains(&c)) {
+        return (None, keys);
+    }
+
+    let len = keys
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(keys.len());
+    let count = keys[..len].bytes().fold(0usize, |n, d| {
+        n.saturating_mul(10).saturating_add(usize::from(d - b'0'))
+    });
+
+    (Some(count), &keys[len..])
+}
+
+/// counts before and after the operator are multiplied, `2d3w` deletes 6 words
+fn multiply(a: Option<usize>, b: Option<usize>) -> Option<usize> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.saturating_mul(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+pub fn parse(keys: &str) -> Parsed {
+    let (count, rest) = take_count(keys);
+
+    let first = match rest.chars().next() {
+        Some(c) => c,
+        None => return Parsed::Pending,
+    };
+    let after_first = &rest[first.len_utf8()..];
+
+    if let Some(expanded) = shorthand(first) {
+        let prefix = &keys[..keys.len() - rest.len()];
+        return parse(&format!("{}{}{}", prefix, expanded, after_first));
+    }
+
+    if let Some(op) = operator(first) {
+        let (op_count, motion_keys) = take_count(after_first);
+        let count = multiply(count, op_count);
+
+        // doubled operator works on lines
+        if motion_keys.starts_with(first) {
+            if motion_keys.len() > first.len_utf8() {
+                return Parsed::Invalid;
+            }
+            return Parsed::Done(Action::Operate {
+                op,
+                motion: Motions::Lines,
+                count,
+            });
+        }
+
+        let parsed = if motion_keys.starts_with(['i', 'a']) {
+            object(motion_keys)
+        } else {
+            motion(motion_keys)
+        };
+
+        return match parsed {
+            MotionParse::Pending => Parsed::Pending,
+            MotionParse::Done(motion) => Parsed::Done(Action::Operate {
+                op,
+                motion: with_count(motion, count),
+                count,
+            }),
+            MotionParse::Invalid => Parsed::Invalid,
+        };
+    }
+
+    match motion(rest) {
+        MotionParse::Pending => Parsed::Pending,
+        MotionParse::Done(motion) => Parsed::Done(Action::Move {
+            motion: with_count(motion, count),
+            count,
+        }),
+        MotionParse::Invalid if after_first.is_empty() => {
+            Parsed::Done(Action::Key { key: first, count })
+        }
+        MotionParse::Invalid => Parsed::Invalid,
+    }
+}
+
+/// in visual mode keys are a motion or a text object that moves the selection, any other key
+/// is left to the editor
+pub fn parse_visual(keys: &str) -> Parsed {
+    let (count, rest) = take_count(keys);
+
+    let first = match rest.chars().next() {
+        Some(c) => c,
+        None => return Parsed::Pending,
+    };
+    let after_first = &rest[first.len_utf8()..];
+
+    let parsed = if first == 'i' || first == 'a' {
+        object(rest)
+    } else {
+        motion(rest)
+    };
+
+    match parsed {
+        MotionParse::Pending => Parsed::Pending,
+        MotionParse::Done(motion) => Parsed::Done(Action::Move {
+            motion: with_count(motion, count),
+            count,
+        }),
+        MotionParse::Invalid if after_first.is_empty() => {
+            Parsed::Done(Action::Key { key: first, count })
+        }
+        MotionParse::Invalid => Parsed::Invalid,
+    }
+}
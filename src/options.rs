@@ -0,0 +1,232 @@
+use crate::status;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Bool(bool),
+    Number(usize),
+    Text(String),
+}
+
+/// full option names with their short names
+const NAMES: &[(&str, &str)] = &[
+    ("history", "hi"),
+    ("osc52", "osc"),
+    ("clipcopy", "ccp"),
+    ("clippaste", "cpa"),
+    ("tabstop", "ts"),
+    ("shiftwidth", "sw"),
+    ("expandtab", "et"),
+    ("number", "nu"),
+    ("relativenumber", "rnu"),
+    ("statusline", "stl"),
+    ("wrap", "wrap"),
+    ("linebreak", "lbr"),
+    ("showbreak", "sbr"),
+];
+
+/// runtime options changed with `:set`
+#[derive(Clone, Debug)]
+pub struct Options {
+    /// count of remembered command lines
+    pub history: usize,
+    /// `"+` and `"*` are sent to the terminal with the OSC 52 escape
+    pub osc52: bool,
+    /// commands that copy to and paste from the system clipboard, e.g. `wl-copy` and
+    /// `wl-paste -n`, not used when empty
+    pub clipcopy: String,
+    pub clippaste: String,
+    /// count of screen columns between tab stops
+    pub tabstop: usize,
+    /// count of screen columns a shift indents by, tabstop is used when it's 0
+    pub shiftwidth: usize,
+    /// indentation is made of spaces instead of tabs
+    pub expandtab: bool,
+    /// line numbers are shown in a gutter before the text
+    pub number: bool,
+    /// line numbers are counted from the cursor line, with number the cursor line keeps it's
+    /// own number
+    pub relativenumber: bool,
+    /// layout of the status line, an empty one leaves the status line blank
+    pub statusline: String,
+    /// lines wider than the screen continue on the next rows instead of being cut
+    pub wrap: bool,
+    /// wrapped lines are split after white space, not in the middle of a word
+    pub linebreak: bool,
+    /// text shown at the start of rows that continue a wrapped line
+    pub showbreak: String,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            history: 50,
+            osc52: true,
+            clipcopy: String::new(),
+            clippaste: String::new(),
+            tabstop: 8,
+            shiftwidth: 8,
+            expandtab: false,
+            number: false,
+            relativenumber: false,
+            statusline: status::DEFAULT_FORMAT.to_string(),
+            wrap: true,
+            linebreak: false,
+            showbreak: String::new(),
+        }
+    }
+
+    /// screen columns of one level of indentation
+    pub fn shift_width(&self) -> usize {
+        if self.shiftwidth == 0 {
+            self.tabstop
+        } else {
+            self.shiftwidth
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match full_name(name)? {
+            "history" => Some(Value::Number(self.history)),
+            "osc52" => Some(Value::Bool(self.osc52)),
+            "clipcopy" => Some(Value::Text(self.clipcopy.clone())),
+            "clippaste" => Some(Value::Text(self.clippaste.clone())),
+            "tabstop" => Some(Value::Number(self.tabstop)),
+            "shiftwidth" => Some(Value::Number(self.shiftwidth)),
+            "expandtab" => Some(Value::Bool(self.expandtab)),
+            "number" => Some(Value::Bool(self.number)),
+            "relativenumber" => Some(Value::Bool(self.relativenumber)),
+            "statusline" => Some(Value::Text(self.statusline.clone())),
+            "wrap" => Some(Value::Bool(self.wrap)),
+            "linebreak" => Some(Value::Bool(self.linebreak)),
+            "showbreak" => Some(Value::Text(self.showbreak.clone())),
+            _ => None,
+        }
+    }
+
+    fn put(&mut self, name: &str, value: Value) -> Result<(), String> {
+        match (name, value) {
+            ("history", Value::Number(n)) => self.history = n,
+            ("osc52", Value::Bool(b)) => self.osc52 = b,
+            ("clipcopy", Value::Text(t)) => self.clipcopy = t,
+            ("clippaste", Value::Text(t)) => self.clippaste = t,
+            ("tabstop", Value::Number(0)) => return Err("Argument must be positive".to_string()),
+            ("tabstop", Value::Number(n)) => self.tabstop = n,
+            ("shiftwidth", Value::Number(n)) => self.shiftwidth = n,
+            ("expandtab", Value::Bool(b)) => self.expandtab = b,
+            ("number", Value::Bool(b)) => self.number = b,
+            ("relativenumber", Value::Bool(b)) => self.relativenumber = b,
+            ("statusline", Value::Text(t)) => self.statusline = t,
+            ("wrap", Value::Bool(b)) => self.wrap = b,
+            ("linebreak", Value::Bool(b)) => self.linebreak = b,
+            ("showbreak", Value::Text(t)) => self.showbreak = t,
+            _ => return Err(format!("Invalid argument: {}", name)),
+        }
+
+        Ok(())
+    }
+
+    /// applies one `:set` argument: `name`, `noname`, `invname`, `name!`, `name?`,
+    /// `name=value` or `name&` to reset it. returns text to show for queries.
+    pub fn set(&mut self, arg: &str) -> Result<Option<String>, String> {
+        if arg == "all" {
+            return Ok(Some(self.show_all()));
+        }
+
+        let unknown = || format!("Unknown option: {}", arg);
+
+        if let Some((name, value)) = arg.split_once(['=', ':']) {
+            let full = full_name(name).ok_or_else(unknown)?;
+            let value = match self.get(full) {
+                Some(Value::Number(_)) => Value::Number(
+                    value
+                        .parse()
+                        .map_err(|_| format!("Number required after =: {}", arg))?,
+                ),
+                Some(Value::Text(_)) => Value::Text(value.to_string()),
+                _ => return Err(format!("Invalid argument: {}", arg)),
+            };
+            return self.put(full, value).map(|_| None);
+        }
+
+        if let Some(name) = arg.strip_suffix('?') {
+            let full = full_name(name).ok_or_else(unknown)?;
+            return Ok(Some(self.show(full)));
+        }
+
+        if let Some(name) = arg.strip_suffix('&') {
+            let full = full_name(name).ok_or_else(unknown)?;
+            let default = Options::new().get(full).ok_or_else(unknown)?;
+            return self.put(full, default).map(|_| None);
+        }
+
+        let (name, toggle) = match arg.strip_suffix('!') {
+            Some(n) => (n, true),
+            None => (arg, false),
+        };
+
+        if let Some(full) = full_name(name) {
+            return match self.get(full) {
+                Some(Value::Bool(v)) if toggle => self.put(full, Value::Bool(!v)).map(|_| None),
+                Some(Value::Bool(_)) => self.put(full, Value::Bool(true)).map(|_| None),
+                Some(_) if !toggle => Ok(Some(self.show(full))),
+                _ => Err(format!("Invalid argument: {}", arg)),
+            };
+        }
+
+        let (full, value) = if let Some(n) = name.strip_prefix("no") {
+            (full_name(n).ok_or_else(unknown)?, false)
+        } else if let Some(n) = name.strip_prefix("inv") {
+            let full = full_name(n).ok_or_else(unknown)?;
+            (full, self.get(full) != Some(Value::Bool(true)))
+        } else {
+            return Err(unknown());
+        };
+
+        match self.get(full) {
+            Some(Value::Bool(_)) => self.put(full, Value::Bool(value)).map(|_| None),
+            _ => Err(format!("Invalid argument: {}", arg)),
+        }
+    }
+
+    /// options that differ from defaults, like `:set` without arguments
+    pub fn show_changed(&self) -> String {
+        let defaults = Options::new();
+        NAMES
+            .iter()
+            .filter(|(name, _)| self.get(name) != defaults.get(name))
+            .map(|(name, _)| self.show(name))
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    fn show_all(&self) -> String {
+        NAMES
+            .iter()
+            .map(|(name, _)| self.show(name))
+            .collect::<Vec<String>>()
+            .join("  ")
+    }
+
+    fn show(&self, name: &str) -> String {
+        match self.get(name) {
+            Some(Value::Bool(true)) => name.to_string(),
+            Some(Value::Bool(false)) => format!("no{}", name),
+            Some(Value::Number(n)) => format!("{}={}", name, n),
+            Some(Value::Text(t)) => format!("{}={}", name, t),
+            None => String::new(),
+        }
+    }
+}
+
+fn full_name(name: &str) -> Option<&'static str> {
+    NAMES
+        .iter()
+        .find(|(full, short)| *full == name || *short == name)
+        .map(|(full, _)| *full)
+}
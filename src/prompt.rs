@@ -0,0 +1,137 @@
+use crate::window::search::Direction;
+use termion::event::Key;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PromptKind {
+    Command,
+    SaveAs,
+    Search(Direction),
+}
+
+pub enum PromptResult {
+    Submit(String),
+    Cancel,
+    Changed,
+}
+
+/// single line input rendered at the status line, e.g. command line or asking for a file name
+pub struct Prompt {
+    pub kind: PromptKind,
+    pub label: String,
+    pub input: Vec<char>,
+    /// index in the input where next char is typed
+    pub cursor: usize,
+    /// position in the history while browsing it with up and down, and the typed text that
+    /// history entries should start with
+    history_idx: Option<usize>,
+    history_prefix: String,
+}
+
+impl Prompt {
+    pub fn new(kind: PromptKind, label: &str) -> Prompt {
+        Prompt {
+            kind,
+            label: label.to_string(),
+            input: Vec::new(),
+            cursor: 0,
+            history_idx: None,
+            history_prefix: String::new(),
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.input.iter().collect()
+    }
+
+    pub fn set_text(&mut self, text: &str) {
+        self.input = text.chars().collect();
+        self.cursor = self.input.len();
+    }
+
+    /// column of the cursor within the rendered prompt, starting from 1
+    pub fn cursor_col(&self) -> u16 {
+        let input: String = self.input[..self.cursor].iter().collect();
+        (self.label.width() + input.width() + 1) as u16
+    }
+
+    pub fn handle(&mut self, key: Key, history: &[String]) -> PromptResult {
+        match key {
+            Key::Char('\n') => return PromptResult::Submit(self.text()),
+            Key::Esc | Key::Ctrl('c') => return PromptResult::Cancel,
+            Key::Backspace | Key::Ctrl('h') => {
+                if self.input.is_empty() {
+                    return PromptResult::Cancel;
+                }
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                    self.input.remove(self.cursor);
+                }
+            }
+            Key::Delete if self.cursor < self.input.len() => {
+                self.input.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.input.len()),
+            Key::Home | Key::Ctrl('b') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.input.len(),
+            Key::Ctrl('u') => {
+                self.input.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('w') => {
+                let end = self.cursor;
+                while self.cursor > 0 && self.input[self.cursor - 1] == ' ' {
+                    self.cursor -= 1;
+                }
+                while self.cursor > 0 && self.input[self.cursor - 1] != ' ' {
+                    self.cursor -= 1;
+                }
+                self.input.drain(self.cursor..end);
+            }
+            Key::Up => self.browse_history(history, true),
+            Key::Down => self.browse_history(history, false),
+            Key::Char(ch) => {
+                self.input.insert(self.cursor, ch);
+                self.cursor += 1;
+            }
+            _ => {}
+        }
+
+        if !matches!(key, Key::Up | Key::Down) {
+            self.history_idx = None;
+        }
+
+        PromptResult::Changed
+    }
+
+    /// moves to older or newer history entry that starts with the text typed before browsing,
+    /// moving past the newest one brings the typed text back
+    fn browse_history(&mut self, history: &[String], older: bool) {
+        if self.history_idx.is_none() {
+            self.history_prefix = self.text();
+        }
+
+        let matches = |i: &usize| history[*i].starts_with(&self.history_prefix);
+        let next = match (self.history_idx, older) {
+            (None, true) => (0..history.len()).rev().find(matches),
+            (Some(idx), true) => (0..idx).rev().find(matches),
+            (Some(idx), false) => (idx + 1..history.len()).find(matches),
+            (None, false) => None,
+        };
+
+        match next {
+            Some(idx) => {
+                self.history_idx = Some(idx);
+                let entry = history[idx].clone();
+                self.set_text(&entry);
+            }
+            None if !older => {
+                self.history_idx = None;
+                let prefix = self.history_prefix.clone();
+                self.set_text(&prefix);
+            }
+            None => {}
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+
+/// text kept in a register, linewise text is put as whole lines
+#[derive(Clone, Debug, PartialEq)]
+pub struct Register {
+    pub text: String,
+    pub linewise: bool,
+}
+
+/// what the text stored comes from, deletes shift numbered registers
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Store {
+    Yank,
+    Delete,
+}
+
+/// `""` unnamed, `"0` last yank, `"1`-`"9` last deletes, `"a`-`"z` named, `"_` black hole
+/// read-only `"%` with the file name, and `"+` and `"*` that keep a copy of what is sent to
+/// the system clipboard
+#[derive(Debug, Default)]
+pub struct Registers {
+    values: BTreeMap<char, Register>,
+}
+
+impl Registers {
+    pub fn new() -> Registers {
+        Registers {
+            values: BTreeMap::new(),
+        }
+    }
+
+    /// names that can follow `"`
+    pub fn is_valid(name: char) -> bool {
+        name.is_ascii_alphanumeric() || matches!(name, '"' | '_' | '%' | '+' | '*')
+    }
+
+    /// whether text can be stored in the register, None being the unnamed one
+    pub fn is_writable(name: Option<char>) -> bool {
+        name.is_none_or(|n| Registers::is_valid(n) && n != '%')
+    }
+
+    /// stores the text into the register, None being the unnamed one. uppercase names append
+    /// to the register, registers that are not writable are left as they are.
+    pub fn store(&mut self, name: Option<char>, text: &str, linewise: bool, store: Store) {
+        let register = Register {
+            text: text.to_string(),
+            linewise,
+        };
+
+        let name = match name {
+            Some('"') | None => None,
+            Some('_') => return,
+            name if Registers::is_writable(name) => name,
+            _ => return,
+        };
+
+        let stored = match name {
+            Some(name) if name.is_ascii_uppercase() => {
+                let name = name.to_ascii_lowercase();
+                let value = match self.values.remove(&name) {
+                    Some(old) => append(old, register),
+                    None => register,
+                };
+                self.values.insert(name, value.clone());
+                value
+            }
+            Some(name) => {
+                self.values.insert(name, register.clone());
+                register
+            }
+            None if store == Store::Delete => {
+                for n in (1..9).rev() {
+                    let digit = char::from(b'0' + n);
+                    if let Some(value) = self.values.remove(&digit) {
+                        self.values.insert(char::from(b'1' + n), value);
+                    }
+                }
+                self.values.insert('1', register.clone());
+                register
+            }
+            None => {
+                self.values.insert('0', register.clone());
+                register
+            }
+        };
+
+        self.values.insert('"', stored);
+    }
+
+    /// keeps recorded keys in the register without touching the unnamed one, uppercase names
+    /// append
+    pub fn record(&mut self, name: char, text: &str) {
+        let register = Register {
+            text: text.to_string(),
+            linewise: false,
+        };
+
+        let lower = name.to_ascii_lowercase();
+        let value = match self.values.remove(&lower) {
+            Some(old) if name.is_ascii_uppercase() => append(old, register),
+            _ => register,
+        };
+        self.values.insert(lower, value);
+    }
+
+    /// content of the register, None being the unnamed one. file name is the value of `"%`.
+    pub fn get(&self, name: Option<char>, file_name: &str) -> Option<Register> {
+        match name.unwrap_or('"') {
+            '%' if !file_name.is_empty() => Some(Register {
+                text: file_name.to_string(),
+                linewise: false,
+            }),
+            name => self.values.get(&name.to_ascii_lowercase()).cloned(),
+        }
+    }
+
+    /// registers that are not empty, in the order `:registers` shows them
+    pub fn list(&self, file_name: &str) -> Vec<(char, Register)> {
+        let mut res: Vec<(char, Register)> = self
+            .values
+            .iter()
+            .map(|(name, value)| (*name, value.clone()))
+            .collect();
+
+        if let Some(value) = self.get(Some('%'), file_name) {
+            res.push(('%', value));
+        }
+
+        res
+    }
+}
+
+/// appended linewise text makes the register linewise
+fn append(old: Register, new: Register) -> Register {
+    let mut text = old.text;
+    if new.linewise && !old.linewise {
+        text.push('\n');
+    }
+    text.push_str(&new.text);
+    if old.linewise && !new.linewise {
+        text.push('\n');
+    }
+
+    Register {
+        text,
+        linewise: old.linewise || new.linewise,
+    }
+}
@@ -0,0 +1,125 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// default value of the statusline option
+pub const DEFAULT_FORMAT: &str = " %M  %f %m%=%y  %e  %o  %l:%c  %p%% ";
+
+/// what the status line can show about the buffer
+#[derive(Clone, Debug, Default)]
+pub struct Status<'a> {
+    pub mode: &'a str,
+    pub file: &'a str,
+    pub modified: bool,
+    pub filetype: &'a str,
+    pub line: usize,
+    pub col: usize,
+    pub lines: usize,
+    pub line_ending: &'a str,
+    pub encoding: &'a str,
+}
+
+/// status line of the width made from the format. items are `%M` mode, `%f` file name, `%m`
+/// `[+]` when modified, `%y` file type, `%l` line, `%c` column, `%L` count of lines, `%p`
+/// percentage of lines above the cursor, `%o` line ending, `%e` encoding and `%%` for `%`.
+/// text after `%=` is aligned to the right.
+pub fn render(format: &str, status: &Status, width: usize) -> String {
+    let (left, right) = match format.split_once("%=") {
+        Some((l, r)) => (expand(l, status), expand(r, status)),
+        None => (expand(format, status), String::new()),
+    };
+
+    // the right part is kept when the line is too long, the left one loses it's end
+    let right = truncate(&right, width);
+    let left = truncate(&left, width - right.width());
+    let fill = width - left.width() - right.width();
+    format!("{}{}{}", left, " ".repeat(fill), right)
+}
+
+/// graphemes of the text that fit the width
+fn truncate(text: &str, width: usize) -> String {
+    let mut res = String::new();
+    let mut used = 0;
+    for g in text.graphemes(true) {
+        used += g.width();
+        if used > width {
+            break;
+        }
+        res.push_str(g);
+    }
+    res
+}
+
+fn expand(format: &str, status: &Status) -> String {
+    let mut res = String::new();
+    let mut chars = format.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            res.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('M') => res.push_str(status.mode),
+            Some('f') if status.file.is_empty() => res.push_str("[No Name]"),
+            Some('f') => res.push_str(status.file),
+            Some('m') if status.modified => res.push_str("[+]"),
+            Some('m') => {}
+            Some('y') => res.push_str(status.filetype),
+            Some('l') => res.push_str(&status.line.to_string()),
+            Some('c') => res.push_str(&status.col.to_string()),
+            Some('L') => res.push_str(&status.lines.to_string()),
+            Some('p') => {
+                let percent = status.line * 100 / status.lines.max(1);
+                res.push_str(&percent.to_string());
+            }
+            Some('o') => res.push_str(status.line_ending),
+            Some('e') => res.push_str(status.encoding),
+            Some('%') => res.push('%'),
+            Some(other) => {
+                res.push('%');
+                res.push(other);
+            }
+            None => res.push('%'),
+        }
+    }
+
+    res
+}
+
+/// type of the file guessed from it's extension, empty when it's not known
+pub fn filetype(file: &str) -> &'static str {
+    let ext = match std::path::Path::new(file).extension() {
+        Some(e) => e.to_string_lossy().to_lowercase(),
+        None => return "",
+    };
+
+    match ext.as_str() {
+        "rs" => "rust",
+        "py" => "python",
+        "js" | "mjs" => "javascript",
+        "ts" => "typescript",
+        "c" | "h" => "c",
+        "cc" | "cpp" | "hpp" => "cpp",
+        "go" => "go",
+        "java" => "java",
+        "rb" => "ruby",
+        "sh" | "bash" => "sh",
+        "md" => "markdown",
+        "toml" => "toml",
+        "json" => "json",
+        "yaml" | "yml" => "yaml",
+        "html" => "html",
+        "css" => "css",
+        "txt" => "text",
+        _ => "",
+    }
+}
+
+/// line ending of the text, it's found by the first line break
+pub fn line_ending(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(idx) if text[..idx].ends_with('\r') => "dos",
+        _ => "unix",
+    }
+}
@@ -1,114 +1,161 @@
-use crate::window::cursor::Cursor;
-use std::io::{self, Stdout, Write};
-use termion::clear;
-
-use termion::raw::{IntoRawMode, RawTerminal};
+use crate::backend::frame::{Frame, Renderer};
+use crate::backend::{Backend, Clear, CursorShape, Style};
 
+/// everything displayed is drawn into the frame, it's sent to the backend on flush once per
+/// handled input, and only cells that changed since the last flush are printed
 pub struct Stdio {
-    pub stdout: RawTerminal<Stdout>,
+    pub backend: Box<dyn Backend>,
+    pub frame: Frame,
+    renderer: Renderer,
     pub terminal_size: (u16, u16),
+    pub status_message: String,
+    pub status_is_error: bool,
+    /// keys of a normal mode command that is being typed
+    pub pending_keys: String,
 }
 
 impl Stdio {
-    pub fn new() -> Stdio {
-        let stdout = io::stdout().into_raw_mode().unwrap();
-
+    pub fn new(backend: Box<dyn Backend>) -> Stdio {
+        let terminal_size = backend.size();
         Stdio {
-            stdout,
-            terminal_size: termion::terminal_size().unwrap(),
+            backend,
+            frame: Frame::new(terminal_size),
+            renderer: Renderer::new(),
+            terminal_size,
+            status_message: String::new(),
+            status_is_error: false,
+            pending_keys: String::new(),
         }
     }
 
+    /// the screen has a new size, the next frame is drawn from scratch
+    pub fn resize(&mut self, size: (u16, u16)) {
+        let shape = self.frame.shape;
+        self.terminal_size = size;
+        self.frame = Frame::new(size);
+        self.frame.shape = shape;
+        self.renderer.invalidate();
+    }
+
+    /// sends the text to the system clipboard through the terminal
+    pub fn set_clipboard(&mut self, selection: char, text: &str) {
+        self.backend.set_clipboard(selection, text);
+        self.backend.flush();
+    }
+
     pub fn cursor_block(&mut self) {
-        write!(self.stdout, "{}", termion::cursor::SteadyBlock).unwrap();
-        self.stdout.flush().unwrap();
+        self.frame.shape = CursorShape::Block;
     }
     pub fn cursor_bar(&mut self) {
-        write!(self.stdout, "{}", termion::cursor::SteadyBar).unwrap();
-        self.stdout.flush().unwrap();
-    }
-
-    pub fn display_below(&mut self, x: u16, y: u16, data: &String) {
-        self.stdout.suspend_raw_mode().unwrap();
-        write!(
-            self.stdout,
-            "{}{}{}",
-            termion::cursor::Goto(x, y),
-            termion::clear::AfterCursor,
-            data
-        )
-        .unwrap();
-        self.stdout.flush().unwrap();
-
-        self.stdout.activate_raw_mode().unwrap();
-    }
-
-    pub fn goto_line(&mut self, pos: (u16, u16, u16)) {
-        self.display_cursor(pos.0, pos.2);
-        self.goto(pos.0, pos.1);
-    }
-
-    pub fn update_line(&mut self, line: &String, c: &Cursor) {
-        write!(
-            self.stdout,
-            "{}{}{}{}",
-            termion::cursor::Goto(1, c.relative_y),
-            clear::CurrentLine,
-            line,
-            termion::cursor::Goto(c.x + 1, c.relative_y)
-        )
-        .unwrap();
-        self.stdout.flush().unwrap();
-    }
-    pub fn update_line_at(&mut self, line: &String, c: (u16, u16)) {
-        write!(
-            self.stdout,
-            "{}{}{}{}",
-            termion::cursor::Goto(1, c.1),
-            clear::CurrentLine,
-            line,
-            termion::cursor::Goto(c.0 + 1, c.1)
-        )
-        .unwrap();
-        self.stdout.flush().unwrap();
-    }
-
-    pub fn display_segment(&mut self, text: String, c: (u16, u16)) {
-        self.stdout.suspend_raw_mode().unwrap();
-        write!(
-            self.stdout,
-            "{}{}{}{}",
-            termion::cursor::Goto(1, 1),
-            clear::All,
-            text,
-            termion::cursor::Goto(c.0, c.1)
-        )
-        .unwrap();
-
-        self.stdout.flush().unwrap();
-
-        self.stdout.activate_raw_mode().unwrap();
+        self.frame.shape = CursorShape::Bar;
+    }
+
+    /// draws what changed in the frame since the last flush
+    pub fn flush(&mut self) {
+        self.renderer.render(&self.frame, self.backend.as_mut());
+        self.backend.flush();
+    }
+
+    pub fn display_below(&mut self, x: u16, y: u16, data: &str) {
+        self.frame.goto(x, y);
+        self.frame.clear(Clear::AfterCursor);
+        self.frame.print(data, Style::Normal);
+    }
+
+    /// rows the text is shown in, the status line and the message line are below them
+    pub fn text_height(&self) -> u16 {
+        self.terminal_size.1.saturating_sub(2).max(1)
+    }
+
+    /// shows the message line and moves the cursor to the (x, y) place on the screen
+    pub fn goto_line(&mut self, screen: (usize, usize)) {
+        self.display_message();
+        self.goto(cell(screen.0), cell(screen.1));
+    }
+
+    /// status line between the text and the message line
+    pub fn display_status(&mut self, text: &str) {
+        let y = self.terminal_size.1.saturating_sub(1).max(1);
+        let line = format!("{:<1$}", text, usize::from(self.terminal_size.0));
+        self.display_at(1, y, &line, Style::StatusLine);
+    }
+
+    /// draws the row again, the text is made of styled parts
+    pub fn update_line(&mut self, y: u16, line: &[(String, Style)]) {
+        let cursor = self.frame.cursor;
+        self.frame.goto(1, y);
+        self.frame.clear(Clear::CurrentLine);
+        for (text, style) in line.iter() {
+            self.frame.print(text, *style);
+        }
+        self.frame.cursor = cursor;
+    }
+
+    /// draws the screen from scratch, the text is made of styled parts
+    pub fn display_segment(&mut self, text: &[(String, Style)], c: (usize, usize)) {
+        self.frame.goto(1, 1);
+        self.frame.clear(Clear::All);
+        for (part, style) in text.iter() {
+            self.frame.print(part, *style);
+        }
+        self.frame.goto(cell(c.0), cell(c.1));
+    }
+
+    /// draws the text at the place, the rest of the row and the cursor stay as they are
+    pub fn display_at(&mut self, x: u16, y: u16, text: &str, style: Style) {
+        let cursor = self.frame.cursor;
+        self.frame.goto(x, y);
+        self.frame.print(text, style);
+        self.frame.cursor = cursor;
     }
 
     fn goto(&mut self, x: u16, y: u16) {
-        write!(self.stdout, "{}", termion::cursor::Goto(x, y)).unwrap();
-        self.stdout.flush().unwrap();
+        self.frame.goto(x, y);
     }
 
-    fn display_cursor(&mut self, x: u16, abs_y: u16) {
-        let cursor_position_str = format!("x: {} y: {}", x, abs_y);
+    /// message is kept until the next one, so it survives cursor position updates
+    pub fn set_status(&mut self, msg: &str) {
+        self.status_message = msg.to_string();
+        self.status_is_error = false;
+    }
+
+    /// same as status, but highlighted
+    pub fn set_error(&mut self, msg: &str) {
+        self.status_message = msg.to_string();
+        self.status_is_error = true;
+    }
+
+    /// prompt at the status line, cursor is placed at the col within it
+    pub fn display_prompt(&mut self, label: &str, input: &str, col: u16) {
+        self.display_below(1, self.terminal_size.1, &format!("{}{}", label, input));
+        self.goto(col, self.terminal_size.1);
+    }
 
-        let offset = cursor_position_str.len();
-        let x = self.terminal_size.0 - offset as u16;
+    /// message on the left of the last line and keys being typed on the right
+    fn display_message(&mut self) {
+        let offset = self.pending_keys.len() + 1;
+        let x = self.terminal_size.0.saturating_sub(offset as u16).max(1);
         let y = self.terminal_size.1;
-        write!(
-            self.stdout,
-            "{}{}{}",
-            termion::cursor::Goto(x, y),
-            clear::CurrentLine,
-            cursor_position_str,
-        )
-        .unwrap();
-        self.stdout.flush().unwrap();
+        let msg: String = self
+            .status_message
+            .chars()
+            .take(usize::from(x.saturating_sub(2)))
+            .collect();
+        let style = if self.status_is_error {
+            Style::Error
+        } else {
+            Style::Normal
+        };
+
+        self.frame.goto(1, y);
+        self.frame.clear(Clear::CurrentLine);
+        self.frame.print(&msg, style);
+        self.frame.goto(x, y);
+        self.frame.print(&self.pending_keys, Style::Normal);
     }
 }
+
+/// screen coordinate of a column or a row of the text, ones past the screen stay past it
+fn cell(v: usize) -> u16 {
+    u16::try_from(v).unwrap_or(u16::MAX)
+}
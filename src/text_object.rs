@@ -0,0 +1,437 @@
+use crate::motion::{self, Walker};
+use crate::window::piece_table::PieceTable;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextObject {
+    /// `w` and `W`
+    Word { big: bool },
+    /// `s`
+    Sentence,
+    /// `p`
+    Paragraph,
+    /// `"`, `'` and `` ` ``
+    Quote(char),
+    /// `(`, `[`, `{` and `<` with their closing pairs
+    Pair(char, char),
+    /// `t`, XML/HTML element
+    Tag,
+}
+
+impl TextObject {
+    /// object typed after `i` or `a`
+    pub fn from_key(key: char) -> Option<TextObject> {
+        match key {
+            'w' => Some(TextObject::Word { big: false }),
+            'W' => Some(TextObject::Word { big: true }),
+            's' => Some(TextObject::Sentence),
+            'p' => Some(TextObject::Paragraph),
+            '"' | '\'' | '`' => Some(TextObject::Quote(key)),
+            '(' | ')' | 'b' => Some(TextObject::Pair('(', ')')),
+            '[' | ']' => Some(TextObject::Pair('[', ']')),
+            '{' | '}' | 'B' => Some(TextObject::Pair('{', '}')),
+            '<' | '>' => Some(TextObject::Pair('<', '>')),
+            't' => Some(TextObject::Tag),
+            _ => None,
+        }
+    }
+
+    /// document range (from, to) of the object around the offset, and whether it's made of
+    /// whole lines. inner leaves out surrounding white space, quotes, brackets or tags.
+    pub fn range(
+        &self,
+        data: &PieceTable,
+        offset: usize,
+        inner: bool,
+        count: usize,
+    ) -> Option<(usize, usize, bool)> {
+        let count = count.max(1);
+
+        let (from, to) = match *self {
+            TextObject::Word { big } => word(data, offset, inner, big, count)?,
+            TextObject::Sentence => sentence(data, offset, inner)?,
+            TextObject::Paragraph => return paragraph(data, offset, inner, count),
+            TextObject::Quote(quote) => quoted(data, offset, quote, inner)?,
+            TextObject::Pair(open, close) => {
+                let (mut from, mut to) = pair(data, offset, open, close)?;
+                for _ in 1..count {
+                    (from, to) = pair(data, from.checked_sub(1)?, open, close)?;
+                }
+                if inner {
+                    inside(data, from + open.len_utf8(), to - close.len_utf8())
+                } else {
+                    (from, to)
+                }
+            }
+            TextObject::Tag => {
+                let (mut outer, mut inner_range) = tag(data, offset)?;
+                for _ in 1..count {
+                    (outer, inner_range) = tag(data, outer.0.checked_sub(1)?)?;
+                }
+                if inner {
+                    inner_range
+                } else {
+                    outer
+                }
+            }
+        };
+
+        Some((from, to, false))
+    }
+}
+
+/// line with the offset as (document offset where it starts, value without the line break)
+fn line_of(data: &PieceTable, offset: usize) -> Option<(usize, String)> {
+    let ln = data.line_at(offset.min(data.len().saturating_sub(1)));
+    let node = data.get_lines(ln, ln).front().cloned()?;
+    let value = node.value.strip_suffix('\n').unwrap_or(&node.value);
+    Some((node.offset, value.to_string()))
+}
+
+fn is_word(c: char, big: bool) -> bool {
+    big || c.is_alphanumeric() || c == '_'
+}
+
+/// runs of items of the same kind in the line: words, punctuation or white space
+fn runs(line: &str, big: bool) -> Vec<(usize, usize)> {
+    let kind = |c: char| {
+        if c.is_whitespace() {
+            0
+        } else if is_word(c, big) {
+            1
+        } else {
+            2
+        }
+    };
+
+    let mut res: Vec<(usize, usize)> = Vec::new();
+    let mut prev = None;
+    for (i, c) in line.char_indices() {
+        match res.last_mut() {
+            Some(last) if prev == Some(kind(c)) => last.1 = i + c.len_utf8(),
+            _ => res.push((i, i + c.len_utf8())),
+        }
+        prev = Some(kind(c));
+    }
+
+    res
+}
+
+fn is_blank(line: &str, run: (usize, usize)) -> bool {
+    line[run.0..run.1].starts_with(char::is_whitespace)
+}
+
+/// `iw` takes count runs of words or white space, `aw` adds white space after the words, or
+/// before them when there is none after
+fn word(
+    data: &PieceTable,
+    offset: usize,
+    inner: bool,
+    big: bool,
+    count: usize,
+) -> Option<(usize, usize)> {
+    let (start, line) = line_of(data, offset)?;
+    let runs = runs(&line, big);
+    let col = offset.checked_sub(start)?;
+    let first = runs.iter().position(|r| r.0 <= col && col < r.1)?;
+
+    let (from, to) = if inner {
+        let last = (first + count - 1).min(runs.len() - 1);
+        (runs[first].0, runs[last].1)
+    } else {
+        let mut idx = first;
+        let mut words = 0;
+        let mut to = runs[first].1;
+        // on white space, it's taken together with the word after it
+        while idx < runs.len() && words < count {
+            if !is_blank(&line, runs[idx]) {
+                words += 1;
+            }
+            to = runs[idx].1;
+            idx += 1;
+        }
+        if idx < runs.len() && is_blank(&line, runs[idx]) && !is_blank(&line, runs[first]) {
+            to = runs[idx].1;
+            (runs[first].0, to)
+        } else if !is_blank(&line, runs[first]) && first > 0 && is_blank(&line, runs[first - 1]) {
+            (runs[first - 1].0, to)
+        } else {
+            (runs[first].0, to)
+        }
+    };
+
+    Some((start + from, start + to))
+}
+
+/// sentences end with `.`, `!` or `?` followed by white space, paragraph bounds end them too
+fn sentence(data: &PieceTable, offset: usize, inner: bool) -> Option<(usize, usize)> {
+    let (first, last, _) = paragraph(data, offset, true, 1)?;
+    let text = data.text_at(first, last - first);
+    let col = offset - first;
+
+    // (start, end of the sentence, end of the white space after it)
+    let mut sentences: Vec<(usize, usize, usize)> = Vec::new();
+    let mut start = 0;
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        let (idx, c) = chars[i];
+        let ends =
+            matches!(c, '.' | '!' | '?') && chars.get(i + 1).is_none_or(|(_, n)| n.is_whitespace());
+        if ends || i + 1 == chars.len() {
+            let end = idx + c.len_utf8();
+            let mut j = i + 1;
+            while j < chars.len() && chars[j].1.is_whitespace() {
+                j += 1;
+            }
+            let after = chars.get(j).map_or(text.len(), |(n, _)| *n);
+            sentences.push((start, end, after));
+            start = after;
+            i = j;
+            continue;
+        }
+        i += 1;
+    }
+
+    let s = sentences.iter().find(|s| col < s.2.max(s.1))?;
+    let to = if inner { s.1 } else { s.2 };
+    Some((first + s.0, first + to))
+}
+
+fn is_empty_line(data: &PieceTable, ln: usize) -> bool {
+    match data.line_offset(ln) {
+        Some(start) => motion::line_end(data, ln) == start,
+        None => true,
+    }
+}
+
+/// `ip` takes the block of lines that are all empty or all not empty, `ap` adds empty lines
+/// after it, or before it when there are none after
+fn paragraph(
+    data: &PieceTable,
+    offset: usize,
+    inner: bool,
+    count: usize,
+) -> Option<(usize, usize, bool)> {
+    let last_ln = data.line_count();
+    let ln = data.line_at(offset).min(last_ln);
+    let empty = is_empty_line(data, ln);
+
+    let mut first = ln;
+    while first > 1 && is_empty_line(data, first - 1) == empty {
+        first -= 1;
+    }
+
+    let block_end = |from: usize| {
+        let kind = is_empty_line(data, from);
+        let mut end = from;
+        while end < last_ln && is_empty_line(data, end + 1) == kind {
+            end += 1;
+        }
+        end
+    };
+
+    let mut last = block_end(ln);
+    for _ in 1..count {
+        if last >= last_ln {
+            break;
+        }
+        last = block_end(last + 1);
+    }
+
+    if !inner {
+        if last < last_ln {
+            last = block_end(last + 1);
+        } else if first > 1 && !empty {
+            first = (1..first)
+                .rev()
+                .take_while(|l| is_empty_line(data, *l))
+                .last()
+                .unwrap_or(first);
+        }
+    }
+
+    let from = data.line_offset(first)?;
+    let to = data.line_offset(last + 1).unwrap_or(data.len());
+    Some((from, to, true))
+}
+
+/// quotes are paired from the start of the line, escaped ones are skipped. when the cursor
+/// is before the first quote, the first pair after it is taken.
+fn quoted(data: &PieceTable, offset: usize, quote: char, inner: bool) -> Option<(usize, usize)> {
+    let (start, line) = line_of(data, offset)?;
+    let col = offset.checked_sub(start)?;
+
+    let mut quotes = Vec::new();
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        if c == quote && !escaped {
+            quotes.push(i);
+        }
+        escaped = c == '\\' && !escaped;
+    }
+
+    let (open, close) = quotes
+        .chunks_exact(2)
+        .map(|p| (p[0], p[1]))
+        .find(|p| col <= p.1)?;
+
+    if inner {
+        return Some((start + open + 1, start + close));
+    }
+
+    // white space after the closing quote is taken, or before the opening one
+    let rest = &line[close + 1..];
+    let after = rest.len() - rest.trim_start().len();
+    if after > 0 {
+        return Some((start + open, start + close + 1 + after));
+    }
+    let before_text = &line[..open];
+    let before = before_text.len() - before_text.trim_end().len();
+    Some((start + open - before, start + close + 1))
+}
+
+/// brackets around the offset, the one under the cursor counts as well
+fn pair(data: &PieceTable, offset: usize, open: char, close: char) -> Option<(usize, usize)> {
+    let mut walker = Walker::new(data, offset);
+
+    let from = if walker.char() == Some(open) {
+        walker.offset
+    } else {
+        // closing bracket under the cursor is the one of the pair
+        let mut depth = 0;
+        loop {
+            match walker.char() {
+                Some(c) if c == close && walker.offset != offset => depth += 1,
+                Some(c) if c == open => {
+                    if depth == 0 {
+                        break walker.offset;
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+            if !walker.back() {
+                return None;
+            }
+        }
+    };
+
+    let mut walker = Walker::new(data, from);
+    let mut depth = 0;
+    loop {
+        match walker.char()? {
+            c if c == open => depth += 1,
+            c if c == close => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some((from, walker.offset + c.len_utf8()));
+                }
+            }
+            _ => {}
+        }
+        walker.forward();
+    }
+}
+
+/// inside of brackets, a line break after the opening one and the indentation before the
+/// closing one are left out, so `ci{` keeps the braces on their own lines
+fn inside(data: &PieceTable, from: usize, to: usize) -> (usize, usize) {
+    let mut walker = Walker::new(data, from);
+    let from = match walker.char() {
+        Some('\n') if from < to => from + 1,
+        _ => from,
+    };
+
+    walker.offset = to;
+    let mut start = to;
+    while walker.back() && walker.offset >= from {
+        match walker.char() {
+            Some(' ') | Some('\t') => start = walker.offset,
+            Some('\n') => return (from, start.max(from)),
+            _ => break,
+        }
+    }
+
+    (from, to)
+}
+
+/// tag that starts at the offset as (name, closing, offset after the `>`), self closing
+/// tags, comments and declarations are not elements
+fn read_tag(data: &PieceTable, offset: usize) -> Option<(String, bool, usize)> {
+    let mut walker = Walker::new(data, offset);
+    if walker.char() != Some('<') {
+        return None;
+    }
+
+    let mut text = String::new();
+    loop {
+        walker.forward();
+        match walker.char()? {
+            '>' => break,
+            '<' => return None,
+            c => text.push(c),
+        }
+    }
+
+    if text.ends_with('/') {
+        return None;
+    }
+    let (closing, text) = match text.strip_prefix('/') {
+        Some(rest) => (true, rest),
+        None => (false, text.as_str()),
+    };
+    let name: String = text
+        .chars()
+        .take_while(|c| c.is_alphanumeric() || matches!(c, '-' | ':' | '_' | '.'))
+        .collect();
+
+    if name.is_empty() {
+        return None;
+    }
+    Some((name, closing, walker.offset + 1))
+}
+
+/// element around the offset as its outer and inner ranges
+#[allow(clippy::type_complexity)]
+fn tag(data: &PieceTable, offset: usize) -> Option<((usize, usize), (usize, usize))> {
+    // going back, closed elements are skipped until an opening tag without a pair is found
+    let mut walker = Walker::new(data, offset);
+    let mut closed: Vec<String> = Vec::new();
+    let (name, open_from, open_to) = loop {
+        if walker.char() == Some('<') {
+            if let Some((name, closing, end)) = read_tag(data, walker.offset) {
+                // closing tag under the cursor belongs to the element that is taken
+                if closing {
+                    if end <= offset {
+                        closed.push(name);
+                    }
+                } else if closed.last() == Some(&name) {
+                    closed.pop();
+                } else if closed.is_empty() {
+                    break (name, walker.offset, end);
+                }
+            }
+        }
+        if !walker.back() {
+            return None;
+        }
+    };
+
+    // going forward, elements with the same name are counted until the closing tag
+    let mut walker = Walker::new(data, open_to);
+    let mut depth = 0;
+    loop {
+        if walker.char()? == '<' {
+            if let Some((n, closing, end)) = read_tag(data, walker.offset) {
+                if n == name && !closing {
+                    depth += 1;
+                } else if n == name && depth == 0 {
+                    return Some(((open_from, end), (open_to, walker.offset)));
+                } else if n == name {
+                    depth -= 1;
+                }
+            }
+        }
+        walker.forward();
+    }
+}
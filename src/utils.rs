@@ -1,5 +1,7 @@
-use std::fs::File;
-use std::io::{Error, Read};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Error, ErrorKind, Read, Write};
+use std::os::unix::fs::{fchown, MetadataExt};
+use std::path::Path;
 
 pub fn file_content(path: &std::path::PathBuf) -> Result<String, Error> {
     let mut f = File::open(path)?;
@@ -8,3 +10,57 @@ pub fn file_content(path: &std::path::PathBuf) -> Result<String, Error> {
     f.read_to_string(&mut content)?;
     Ok(content)
 }
+
+/// writes data next to the target in a temp file, syncs it and renames it over the target, so
+/// a crash in the middle of the save never leaves a half written file behind.
+/// permissions and ownership of the existing file are copied to the temp file before rename,
+/// ownership is best effort as only privileged users can give a file away.
+/// returns the number of written bytes.
+pub fn write_file_atomic(path: &Path, data: &str) -> Result<usize, Error> {
+    // follow symlinks, otherwise rename would replace the link itself
+    let target = match fs::canonicalize(path) {
+        Ok(p) => p,
+        Err(e) if e.kind() == ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e),
+    };
+
+    let file_name = match target.file_name() {
+        Some(n) => n.to_string_lossy().to_string(),
+        None => return Err(Error::new(ErrorKind::InvalidInput, "invalid file name")),
+    };
+    let tmp_path = target.with_file_name(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+    let original = fs::metadata(&target).ok();
+
+    let res = (|| {
+        let mut tmp = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)?;
+
+        tmp.write_all(data.as_bytes())?;
+
+        if let Some(meta) = &original {
+            tmp.set_permissions(meta.permissions())?;
+            let _ = fchown(&tmp, Some(meta.uid()), Some(meta.gid()));
+        }
+
+        tmp.sync_all()?;
+        fs::rename(&tmp_path, &target)?;
+
+        // persist the rename itself
+        let dir = match target.parent() {
+            Some(d) if !d.as_os_str().is_empty() => d,
+            _ => Path::new("."),
+        };
+        File::open(dir)?.sync_all()?;
+
+        Ok(data.len())
+    })();
+
+    if res.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+
+    res
+}
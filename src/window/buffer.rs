@@ -1,113 +1,234 @@
 use crate::codes;
-use crate::constants;
-use crate::logger;
-use crate::motion::Motions;
+use crate::motion::{self, MotionKind, Motions};
+use crate::normal::Operator;
+use crate::options::Options;
+use crate::status;
 use crate::stdio::Stdio;
 use crate::utils;
+use crate::window::columns;
 use crate::window::cursor::Cursor;
+use crate::window::history::{Change, History};
 use crate::window::piece_table::PieceTable;
+use crate::window::search::{self, Direction, Pattern, Replacement};
 use crate::window::segment::{Segment, SegmentNode};
+use crate::window::selection::{Selection, SelectionKind};
+use crate::window::wrap::Layout;
 
-use termion::terminal_size;
+use crate::backend::Style;
 
 pub struct Buffer {
     pub data: PieceTable,
     pub stdio: Stdio,
-    pub lines: usize,
     pub cursor: Cursor,
     pub segment: Segment,
+    pub selection: Option<Selection>,
+    /// pattern whose matches are highlighted
+    pub highlight: Option<Pattern>,
+    /// first and last lines of the last selection, used as '< and '> in command ranges
+    pub visual_marks: Option<(usize, usize)>,
+    pub options: Options,
+    /// there are changes since the last save
+    pub modified: bool,
+    /// `unix` or `dos` line breaks of the file, they are kept as they are
+    pub line_ending: &'static str,
+    history: History,
+    /// history position of the text that is in the file, none when it was saved in the middle
+    /// of a step
+    saved: Option<usize>,
     current_line: Result<SegmentNode, String>,
     file_path: std::path::PathBuf,
 }
 
 impl Buffer {
-    pub fn new(path: Option<String>) -> Buffer {
+    pub fn new(path: Option<String>, stdio: Stdio) -> Buffer {
         let file_path = match path {
             Some(v) => std::path::PathBuf::from(v),
             None => "".into(),
         };
 
-        let file = match utils::file_content(&file_path) {
-            Ok(res) => res,
-            Err(_) => String::new(),
-        };
+        let file = utils::file_content(&file_path).unwrap_or_default();
 
-        let terminal_size = terminal_size().unwrap();
         let piece_table = PieceTable::new(&file);
-        let initial_segment = piece_table.get_lines(0, (terminal_size.1 - 1).into());
-        let stdio = Stdio::new();
+        let initial_segment = piece_table.get_lines(1, stdio.text_height().into());
         let current_line = initial_segment.get_line(1).cloned();
 
         let mut buffer = Buffer {
             file_path,
             data: piece_table,
-            lines: file.lines().count().clone(),
             cursor: Cursor {
                 x: 1,
                 vertical_x: 1,
                 relative_y: 1,
                 absolute_y: 1,
+                screen_x: None,
             },
             segment: initial_segment,
+            selection: None,
+            highlight: None,
+            visual_marks: None,
+            options: Options::new(),
+            modified: false,
+            line_ending: status::line_ending(&file),
+            history: History::new(),
+            saved: Some(0),
             stdio,
             current_line,
         };
 
+        buffer.fit_segment();
         buffer.display_segment();
-        buffer.stdio.goto_line((
-            buffer.cursor.x,
-            buffer.cursor.relative_y,
-            buffer.cursor.absolute_y,
-        ));
+        buffer.display_motion(buffer.cursor.x);
         buffer.stdio.cursor_block();
+        buffer.stdio.flush();
 
         buffer
     }
 
-    pub fn save(&mut self) {
+    pub fn has_file_path(&self) -> bool {
+        !self.file_path.as_os_str().is_empty()
+    }
+
+    pub fn file_name(&self) -> String {
+        self.file_path.display().to_string()
+    }
+
+    /// saves the buffer under a new name, it's used as the file name from now on
+    pub fn save_as(&mut self, path: &str) -> bool {
+        self.file_path = std::path::PathBuf::from(path);
+        self.save()
+    }
+
+    /// writes the piece table content into the file_path and reports result in the status line
+    pub fn save(&mut self) -> bool {
+        if !self.has_file_path() {
+            self.set_error("No file name");
+            return false;
+        }
+
+        let path = self.file_path.clone();
+        self.write(&path)
+    }
+
+    /// writes into another file, the buffer keeps it's name unless it had none
+    pub fn write_to(&mut self, path: &str) -> bool {
+        if !self.has_file_path() {
+            return self.save_as(path);
+        }
+
+        self.write(std::path::Path::new(path))
+    }
+
+    fn write(&mut self, path: &std::path::Path) -> bool {
         let data = self.data.get_string();
+        match utils::write_file_atomic(path, &data) {
+            Ok(bytes) => {
+                if path == self.file_path {
+                    self.modified = false;
+                    self.saved = self.history.position();
+                }
+                self.set_status(&format!(
+                    "\"{}\" {}L, {}B written",
+                    path.display(),
+                    data.lines().count(),
+                    bytes
+                ));
+                true
+            }
+            Err(e) => {
+                self.set_error(&format!("Error writing \"{}\": {}", path.display(), e));
+                false
+            }
+        }
+    }
+
+    /// replaces content of the buffer with the file, history starts from scratch
+    pub fn open(&mut self, path: &str) {
+        self.file_path = std::path::PathBuf::from(path);
 
-        logger::log_to_file(&format!("{}", &data));
+        let msg = match utils::file_content(&self.file_path) {
+            Ok(file) => {
+                self.data = PieceTable::new(&file);
+                self.line_ending = status::line_ending(&file);
+                format!("\"{}\" {}L, {}B", path, file.lines().count(), file.len())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                self.data = PieceTable::new("");
+                self.line_ending = "unix";
+                format!("\"{}\" [New]", path)
+            }
+            Err(e) => {
+                self.data = PieceTable::new("");
+                self.line_ending = "unix";
+                format!("\"{}\" {}", path, e)
+            }
+        };
+
+        self.history = History::new();
+        self.saved = Some(0);
+        self.selection = None;
+        self.modified = false;
+        self.segment = Segment::new();
+        self.stdio.set_status(&msg);
+        self.goto(1, 1);
+    }
+
+    pub fn set_status(&mut self, msg: &str) {
+        self.stdio.set_status(msg);
+        self.display_motion(self.cursor.col());
+    }
+
+    /// keys of a command that is not complete yet
+    pub fn set_pending(&mut self, keys: &str) {
+        if self.stdio.pending_keys != keys {
+            self.stdio.pending_keys = keys.to_string();
+            self.display_motion(self.cursor.col());
+        }
+    }
+
+    pub fn set_error(&mut self, msg: &str) {
+        self.stdio.set_error(msg);
+        self.display_motion(self.cursor.col());
     }
 
     pub fn motion(&mut self, motion: Motions) {
         match motion {
+            // the view scrolls when the cursor is placed on a line that is not on the screen
             Motions::Down => {
-                if self.cursor.relative_y + 1 >= self.stdio.terminal_size.1 {
-                    self.data.next_line(&mut self.segment);
-                    self.display_segment();
+                if self.cursor.absolute_y >= self.data.line_count() {
+                    return;
                 }
-                self.cursor.move_down(self.stdio.terminal_size.1);
+                self.keep_screen_x();
+                self.cursor.absolute_y += 1;
                 self.update_cur_line();
                 self.display_motion(self.cursor.vertical_x);
             }
             Motions::Up => {
-                let ln = self.segment.front().unwrap().line_number;
-                if usize::from(self.cursor.absolute_y) <= ln {
-                    self.data.prev_line(&mut self.segment);
-                    self.display_segment();
-                }
-                if self.cursor.absolute_y as i16 - 1 >= 1 {
-                    self.cursor.move_up();
+                self.keep_screen_x();
+                if self.cursor.absolute_y > 1 {
+                    self.cursor.absolute_y -= 1;
                 }
 
                 self.update_cur_line();
                 self.display_motion(self.cursor.vertical_x);
             }
-            Motions::Left => {
-                if self.cursor.x > self.cursor.vertical_x {
-                    self.cursor.set_x(self.cursor.vertical_x);
-                    self.cursor.vertical_x = self.stdio.terminal_size.0;
+            Motions::RowDown | Motions::RowUp => {
+                let offset = self.offset_of(self.position());
+                if let Some(target) = self.target(motion, offset, 1) {
+                    let (ln, col) = self.position_of(target);
+                    self.cursor.absolute_y = ln;
+                    self.update_cur_line();
+                    self.cursor.set_col(col);
+                    self.display_motion(col);
                 }
-
+            }
+            Motions::Left => {
+                self.cursor.set_x(self.cursor.col());
                 self.cursor.move_left();
+                self.cursor.vertical_x = self.cursor.x;
                 self.display_motion(self.cursor.x);
             }
             Motions::Right => {
-                if self.cursor.x > self.cursor.vertical_x {
-                    self.cursor.set_x(self.cursor.vertical_x);
-                    self.cursor.vertical_x = self.stdio.terminal_size.0;
-                }
+                self.cursor.set_x(self.cursor.col());
 
                 let node = self.current_line.as_ref().expect("should be valid ln");
                 let ln_len = self.get_ln_len(&node.value.clone());
@@ -115,144 +236,986 @@ impl Buffer {
                 if self.cursor.x < ln_len {
                     self.cursor.move_right()
                 }
+                self.cursor.vertical_x = self.cursor.x;
                 self.display_motion(self.cursor.x);
             }
+            other => {
+                self.move_by(other, 1);
+            }
         }
     }
 
+    /// moves the cursor count times, steps in the 4 directions keep the column like before,
+    /// other motions jump to where they end in the piece table
+    /// returns false when the motion can't move, `$` on the last item of the line doesn't fail
+    pub fn move_by(&mut self, motion: Motions, count: usize) -> bool {
+        match motion {
+            Motions::Up
+            | Motions::Down
+            | Motions::Left
+            | Motions::Right
+            | Motions::RowDown
+            | Motions::RowUp => {
+                let from = self.position();
+                for _ in 0..count.max(1) {
+                    self.motion(motion);
+                }
+                self.position() != from
+            }
+            Motions::Lines => true,
+            _ => {
+                let offset = self.offset_of(self.position());
+                match self.target(motion, offset, count) {
+                    Some(target) => {
+                        // the cursor stays on an item, not after the last one
+                        self.goto_offset(target.min(self.data.len().saturating_sub(1)));
+                        true
+                    }
+                    None => motion == Motions::LineEnd,
+                }
+            }
+        }
+    }
+
+    /// document range the operator takes when the motion is repeated count times from the
+    /// cursor, and whether it's made of whole lines
+    fn motion_range(
+        &self,
+        op: Operator,
+        motion: Motions,
+        count: usize,
+    ) -> Option<(usize, usize, bool)> {
+        let offset = self.offset_of(self.position());
+        if let Motions::Object { object, inner } = motion {
+            return object
+                .range(&self.data, offset, inner, count)
+                .filter(|r| r.0 < r.1);
+        }
+
+        let target = self.target(motion, offset, count)?;
+
+        if motion.kind() == MotionKind::Linewise {
+            let first = self.data.line_at(offset.min(target));
+            let last = self.data.line_at(offset.max(target));
+            let from = self.data.line_offset(first)?;
+            let to = self.data.line_offset(last + 1).unwrap_or(self.data.len());
+            return Some((from, to, true));
+        }
+
+        let (from, mut to) = (offset.min(target), offset.max(target));
+
+        if motion.kind() == MotionKind::Inclusive {
+            to = motion::Walker::new(&self.data, to)
+                .char()
+                .filter(|c| *c != '\n')
+                .map_or(to, |c| to + c.len_utf8());
+        }
+
+        if matches!(motion, Motions::WordForward | Motions::BigWordForward) {
+            // the word motion stops at the end of the line instead of taking the line break
+            let target_ln = self.data.line_at(to);
+            if target_ln > self.data.line_at(from) && to < self.data.len() {
+                to = motion::line_end(&self.data, target_ln - 1).max(from);
+            }
+
+            // changed words keep white space after them
+            if op == Operator::Change {
+                let text = self.data.text_at(from, to - from);
+                to = from + text.trim_end_matches([' ', '\t']).len();
+            }
+        }
+
+        Some((from, to, false)).filter(|r| r.0 < r.1)
+    }
+
+    /// applies the operator to the text the motion moves over, returns the text and whether
+    /// it's made of whole lines
+    pub fn operate(
+        &mut self,
+        op: Operator,
+        motion: Motions,
+        count: usize,
+    ) -> Option<(String, bool)> {
+        let (from, to, linewise) = self.motion_range(op, motion, count)?;
+        let text = self.data.text_at(from, to - from);
+        let first = self.data.line_at(from);
+        let last = self.data.line_at(to.saturating_sub(1).max(from));
+
+        match op {
+            // the cursor only moves when the text starts before it
+            Operator::Yank => {
+                if linewise && first < self.position().0 {
+                    self.goto(first, self.cursor.col());
+                } else if !linewise {
+                    self.goto_offset(from);
+                }
+            }
+            Operator::Delete => {
+                let mut from = from;
+                // the last line has no line break to take, so the one before it goes
+                if linewise && to == self.data.len() && from > 0 && !text.ends_with('\n') {
+                    from -= 1;
+                }
+
+                self.change(|b| b.delete_text(from, to - from));
+                if linewise {
+                    let offset = motion::first_non_blank(&self.data, first);
+                    self.goto_offset(offset);
+                } else {
+                    self.goto_offset(from);
+                }
+            }
+            Operator::Change => {
+                // changed lines leave an empty line to type in
+                let to = if linewise && text.ends_with('\n') {
+                    to - 1
+                } else {
+                    to
+                };
+
+                self.change(|b| b.delete_text(from, to - from));
+                self.goto_offset(from);
+            }
+            Operator::ShiftRight | Operator::ShiftLeft => {
+                self.shift_lines(first, last, op == Operator::ShiftRight);
+            }
+        }
+
+        Some((text, linewise))
+    }
+
+    /// puts the text count times after the cursor or before it as one undo step, linewise
+    /// text goes below or above the line
+    pub fn put(&mut self, text: &str, linewise: bool, before: bool, count: usize) {
+        if text.is_empty() {
+            return;
+        }
+
+        let mut text = text.repeat(count.max(1));
+        let ln = self.position().0;
+
+        if linewise {
+            if !text.ends_with('\n') {
+                text.push('\n');
+            }
+
+            let below = if before { ln } else { ln + 1 };
+            let offset = match self.data.line_offset(below) {
+                Some(offset) => offset,
+                // the last line has no line break, so the text comes after a new one
+                None => {
+                    text.pop();
+                    text.insert(0, '\n');
+                    self.data.len()
+                }
+            };
+
+            self.change(|b| b.insert_text(&text, offset));
+            let offset = motion::first_non_blank(&self.data, below);
+            self.goto_offset(offset);
+            return;
+        }
+
+        let mut offset = self.offset_of(self.position());
+        if !before {
+            offset = match motion::Walker::new(&self.data, offset).char() {
+                Some(c) if c != '\n' => offset + c.len_utf8(),
+                _ => offset,
+            };
+        }
+
+        self.change(|b| b.insert_text(&text, offset));
+
+        // the cursor ends on the last item put, or at the start of text made of many lines
+        if text.contains('\n') {
+            self.goto_offset(offset);
+        } else {
+            let mut walker = motion::Walker::new(&self.data, offset + text.len());
+            walker.back();
+            self.goto_offset(walker.offset);
+        }
+    }
+
+    /// inserts pasted text at the cursor as one undo step, the cursor ends after it
+    pub fn paste(&mut self, text: &str) {
+        let offset = self.offset_of(self.position());
+        self.change(|b| b.insert_text(text, offset));
+        self.goto_offset(offset + text.len());
+    }
+
+    /// where the motion ends, lines of the screen are known here and not in the piece table
+    fn target(&self, motion: Motions, offset: usize, count: usize) -> Option<usize> {
+        let first = self.segment.front().map_or(1, |n| n.line_number);
+        let last = self.segment.back().map_or(1, |n| n.line_number);
+
+        let ln = match motion {
+            Motions::ScreenTop => (first + count.max(1) - 1).min(last),
+            Motions::ScreenMiddle => first + (last - first) / 2,
+            Motions::ScreenBottom => last.saturating_sub(count.max(1) - 1).max(first),
+            Motions::RowDown => return self.row_target(offset, count, true),
+            Motions::RowUp => return self.row_target(offset, count, false),
+            _ => return motion.target(&self.data, offset, count),
+        };
+
+        Motions::GotoLine.target(&self.data, offset, ln)
+    }
+
+    /// offset count rows of the screen below or above the offset, at the same screen column.
+    /// without wrap each line is one row.
+    fn row_target(&self, offset: usize, count: usize, down: bool) -> Option<usize> {
+        let layout = self.layout();
+        let (mut ln, col) = self.position_of(offset);
+        let mut node = self.data.get_lines(ln, ln).front()?.clone();
+        let mut rows = layout.rows(&node.value);
+
+        let idx = columns::byte_of(&node.value, col);
+        let x = layout.x_of(&node.value, &rows, idx);
+        let mut row = layout.row_of(&rows, idx);
+
+        for _ in 0..count.max(1) {
+            if down && row + 1 < rows.len() {
+                row += 1;
+                continue;
+            }
+            if !down && row > 0 {
+                row -= 1;
+                continue;
+            }
+
+            let next = if down { ln + 1 } else { ln - 1 };
+            match self.data.get_lines(next, next).front() {
+                Some(n) if next >= 1 => {
+                    ln = next;
+                    node = n.clone();
+                    rows = layout.rows(&node.value);
+                    row = if down { 0 } else { rows.len() - 1 };
+                }
+                _ => break,
+            }
+        }
+
+        let target = node.offset + layout.idx_at(&node.value, &rows, row, x);
+        Some(target).filter(|t| *t != offset)
+    }
+
+    /// types the item at the cursor, with expandtab a tab is typed as spaces up to the next
+    /// tab stop
     pub fn edit(&mut self, item: char) {
+        if item == '\t' && self.options.expandtab {
+            let tabstop = self.options.tabstop;
+            let line = self.current_line.as_ref().map_or("", |n| n.value.as_str());
+            let x = columns::screen_col(line, self.cursor.col(), tabstop) - 1;
+            let w = columns::width("\t", x, tabstop);
+            self.change(|b| {
+                for _ in 0..w {
+                    b.apply_edit(' ');
+                }
+            });
+            return;
+        }
+
+        self.change(|b| b.apply_edit(item));
+    }
+
+    /// runs f as one undo step, or as a part of the step that is already open
+    fn change<F: FnOnce(&mut Buffer)>(&mut self, f: F) {
+        let standalone = !self.history.is_open();
+        if standalone {
+            self.begin_change();
+        }
+
+        f(self);
+
+        if standalone {
+            self.end_change();
+        }
+    }
+
+    /// selection starts at the cursor, and follows it until it's cleared
+    pub fn select(&mut self, kind: SelectionKind) {
+        let pos = self.position();
+        match self.selection.as_mut() {
+            Some(s) => s.kind = kind,
+            None => self.selection = Some(Selection::new(kind, pos)),
+        }
+        self.redraw();
+    }
+
+    /// selects the text object around the cursor, returns true when the object is made of
+    /// whole lines and the selection became linewise
+    pub fn select_object(&mut self, motion: Motions, count: usize) -> bool {
+        let (object, inner) = match motion {
+            Motions::Object { object, inner } => (object, inner),
+            _ => return false,
+        };
+
+        let offset = self.offset_of(self.position());
+        let (from, to, linewise) = match object.range(&self.data, offset, inner, count) {
+            Some(r) if r.0 < r.1 => r,
+            _ => return false,
+        };
+
+        let anchor = self.position_of(from);
+        if let Some(selection) = self.selection.as_mut() {
+            selection.anchor = anchor;
+            if linewise {
+                selection.kind = SelectionKind::Line;
+            }
+        }
+
+        let mut walker = motion::Walker::new(&self.data, to);
+        walker.back();
+        self.goto_offset(walker.offset);
+        self.redraw();
+
+        linewise
+    }
+
+    pub fn clear_selection(&mut self) {
+        if self.take_selection().is_some() {
+            self.redraw();
+        }
+    }
+
+    /// removes the selection, it's lines are remembered for '< and '>
+    fn take_selection(&mut self) -> Option<Selection> {
+        let selection = self.selection.take()?;
+        self.visual_marks = Some(selection.lines(self.position()));
+        Some(selection)
+    }
+
+    /// selected text, lines of a block are joined with line breaks
+    pub fn selection_text(&self) -> String {
+        let selection = match self.selection.as_ref() {
+            Some(s) => s,
+            None => return String::new(),
+        };
+
+        let ranges = selection.ranges(self.position(), &self.data);
+        let parts: Vec<String> = ranges
+            .iter()
+            .map(|(offset, len)| self.data.text_at(*offset, *len))
+            .collect();
+
+        parts.join("\n")
+    }
+
+    /// removes selected items and places the cursor where the selection started
+    pub fn delete_selection(&mut self) {
+        let selection = match self.take_selection() {
+            Some(s) => s,
+            None => return,
+        };
+
+        let pos = self.position();
+        let ranges = selection.ranges(pos, &self.data);
+
+        self.change(|b| {
+            for (offset, len) in ranges.iter().rev() {
+                b.delete_text(*offset, *len);
+            }
+        });
+
+        let start = selection.start(pos);
+        self.goto(start.0, start.1);
+    }
+
+    /// adds one level of indentation to selected lines, or removes it
+    pub fn shift_selection(&mut self, right: bool) {
+        let selection = match self.take_selection() {
+            Some(s) => s,
+            None => return,
+        };
+
+        let (first, last) = selection.lines(self.position());
+        self.shift_lines(first, last, right);
+    }
+
+    /// adds one level of indentation to the lines, or removes it. the indentation is made
+    /// again from tabs and spaces, or only spaces with expandtab.
+    fn shift_lines(&mut self, first: usize, last: usize, right: bool) {
+        let lines = self.data.get_lines(first, last);
+        let tabstop = self.options.tabstop;
+        let shift = self.options.shift_width();
+        let spaces = self.options.expandtab;
+
+        self.change(|b| {
+            for node in lines.nodes.iter().rev() {
+                let content = node.value.trim_end_matches('\n');
+                // empty lines are not indented
+                if right && content.is_empty() {
+                    continue;
+                }
+
+                let len = content.len() - content.trim_start_matches([' ', '\t']).len();
+                let old = &content[..len];
+                let width = columns::screen_col(old, len + 1, tabstop) - 1;
+                let width = if right {
+                    width + shift
+                } else {
+                    width.saturating_sub(shift)
+                };
+
+                let new = columns::indent(width, tabstop, spaces);
+                if new != old {
+                    b.delete_text(node.offset, len);
+                    b.insert_text(&new, node.offset);
+                }
+            }
+        });
+
+        self.goto(first, 1);
+    }
+
+    /// swaps case of selected items
+    pub fn toggle_case_selection(&mut self) {
+        let selection = match self.take_selection() {
+            Some(s) => s,
+            None => return,
+        };
+
+        let pos = self.position();
+        let ranges = selection.ranges(pos, &self.data);
+
+        self.change(|b| {
+            for (offset, len) in ranges.iter().rev() {
+                let text = b.data.text_at(*offset, *len);
+                let toggled: String = text
+                    .chars()
+                    .flat_map(|c| -> Box<dyn Iterator<Item = char>> {
+                        if c.is_uppercase() {
+                            Box::new(c.to_lowercase())
+                        } else {
+                            Box::new(c.to_uppercase())
+                        }
+                    })
+                    .collect();
+
+                if toggled != text {
+                    b.delete_text(*offset, *len);
+                    b.insert_text(&toggled, *offset);
+                }
+            }
+        });
+
+        let start = selection.start(pos);
+        self.goto(start.0, start.1);
+    }
+
+    /// moves the cursor to the closest match of the pattern from the position, or back to the
+    /// position when there is none. matches stay highlighted until the highlight is cleared.
+    pub fn search(
+        &mut self,
+        pattern: &Pattern,
+        direction: Direction,
+        from: (usize, usize),
+    ) -> bool {
+        self.highlight = Some(pattern.clone());
+
+        let offset = self.offset_of(from);
+        match search::find(&self.data, pattern, offset, direction) {
+            Some((found, wrapped)) => {
+                let msg = match (wrapped, direction) {
+                    (false, _) => "",
+                    (true, Direction::Forward) => "search hit BOTTOM, continuing at TOP",
+                    (true, Direction::Backward) => "search hit TOP, continuing at BOTTOM",
+                };
+                self.stdio.set_status(msg);
+
+                let (ln, col) = self.position_of(found);
+                self.goto(ln, col);
+                true
+            }
+            None => {
+                self.goto(from.0, from.1);
+                false
+            }
+        }
+    }
+
+    /// replaces matches of a substitution as one undo step, or as a part of the open one.
+    /// the cursor is placed at the start of the line with the last replacement.
+    pub fn replace(&mut self, replacements: &[Replacement]) {
+        let last = match replacements.last() {
+            Some(r) => r.offset,
+            None => return,
+        };
+
+        self.change(|b| {
+            for r in replacements.iter().rev() {
+                b.delete_text(r.offset, r.len);
+                b.insert_text(&r.text, r.offset);
+            }
+        });
+
+        let (ln, _) = self.position_of(last);
+        self.goto(ln, 1);
+    }
+
+    pub fn clear_highlight(&mut self) {
+        if self.highlight.take().is_some() {
+            self.redraw();
+        }
+    }
+
+    /// draws visible lines and the cursor again
+    pub fn redraw(&mut self) {
+        self.display_segment();
+        self.display_motion(self.cursor.col());
+    }
+
+    /// changes made until end_change are undone as one step
+    pub fn begin_change(&mut self) {
+        let pos = self.position();
+        self.history.begin(pos);
+    }
+
+    pub fn end_change(&mut self) {
+        let pos = self.position();
+        self.history.end(pos);
+    }
+
+    pub fn undo(&mut self) {
+        self.end_change();
+
+        let step = match self.history.undo() {
+            Some(s) => s,
+            None => {
+                self.set_status("Already at oldest change");
+                return;
+            }
+        };
+
+        for change in step.changes.iter().rev() {
+            match change {
+                Change::Insert { offset, .. } => self.data.delete(*offset, change.len()),
+                Change::Delete { offset, pieces } => self.data.insert_pieces(*offset, pieces),
+            }
+        }
+
+        self.modified = self.saved.is_none() || self.saved != self.history.position();
+        self.stdio
+            .set_status(&format!("{} changes undone", step.changes.len()));
+        self.goto(step.cursor_before.0, step.cursor_before.1);
+    }
+
+    pub fn redo(&mut self) {
+        self.end_change();
+
+        let step = match self.history.redo() {
+            Some(s) => s,
+            None => {
+                self.set_status("Already at newest change");
+                return;
+            }
+        };
+
+        for change in step.changes.iter() {
+            match change {
+                Change::Insert { offset, pieces } => self.data.insert_pieces(*offset, pieces),
+                Change::Delete { offset, .. } => self.data.delete(*offset, change.len()),
+            }
+        }
+
+        self.modified = self.saved.is_none() || self.saved != self.history.position();
+        self.stdio
+            .set_status(&format!("{} changes redone", step.changes.len()));
+        self.goto(step.cursor_after.0, step.cursor_after.1);
+    }
+
+    /// the terminal has the new (width, height), visible lines are loaded again for it and
+    /// everything is drawn from scratch
+    pub fn resize(&mut self, size: (u16, u16)) {
+        // rows are left for the status line and the message line
+        let size = (size.0.max(1), size.1.max(3));
+        self.stdio.resize(size);
+        self.relayout();
+    }
+
+    /// visible lines are loaded again from the same first line and drawn, options may have
+    /// changed how many of them fit the screen
+    pub fn relayout(&mut self) {
+        let first = self.segment.front().map_or(1, |n| n.line_number);
+        self.load_segment(first);
+        self.update_cur_line();
+        self.redraw();
+    }
+
+    /// moves cursor to the line and column, the segment is loaded again so the line is visible
+    pub fn goto(&mut self, ln: usize, col: usize) {
+        let height = usize::from(self.stdio.text_height());
+        let ln = ln.clamp(1, self.data.line_count());
+
+        let first = self.segment.front().map_or(1, |n| n.line_number);
+        self.load_segment(first);
+        if ln < first || self.segment.get_line(ln).is_err() {
+            self.load_segment(ln.saturating_sub(height / 2).max(1));
+        }
+
+        self.cursor.absolute_y = ln;
+        self.cursor.set_col(col);
+        self.update_cur_line();
+        self.scroll_to_cursor(self.cursor.vertical_x);
+        self.display_segment();
+        self.display_motion(self.cursor.vertical_x);
+    }
+
+    pub fn goto_offset(&mut self, offset: usize) {
+        let (ln, col) = self.position_of(offset);
+        self.goto(ln, col);
+    }
+
+    pub fn position(&self) -> (usize, usize) {
+        (self.cursor.absolute_y, self.cursor.col())
+    }
+
+    /// document offset of the (line, column) position, columns are graphemes of the line
+    fn offset_of(&self, pos: (usize, usize)) -> usize {
+        match self.data.get_lines(pos.0, pos.0).front() {
+            Some(node) => node.offset + columns::byte_of(&node.value, pos.1),
+            None => self.data.len(),
+        }
+    }
+
+    /// (line, column) position of the document offset, an offset inside of a grapheme is at
+    /// it's column
+    fn position_of(&self, offset: usize) -> (usize, usize) {
+        let ln = self.data.line_at(offset);
+        match self.data.get_lines(ln, ln).front() {
+            Some(node) => (ln, columns::col_of(&node.value, offset - node.offset)),
+            None => (ln, 1),
+        }
+    }
+
+    fn insert_text(&mut self, text: &str, offset: usize) {
+        if text.is_empty() {
+            return;
+        }
+
+        self.modified = true;
+        self.data.insert(text, offset);
+        let pieces = self.data.pieces_at(offset, text.len());
+        self.history.push(Change::Insert { offset, pieces });
+    }
+
+    fn delete_text(&mut self, offset: usize, len: usize) {
+        if len == 0 {
+            return;
+        }
+
+        self.modified = true;
+        let pieces = self.data.pieces_at(offset, len);
+        self.data.delete(offset, len);
+        self.history.push(Change::Delete { offset, pieces });
+    }
+
+    fn apply_edit(&mut self, item: char) {
         // we have relative x and absolute y;
         //
-        // we need to go to the ring buffer and find exact line where we currently at
-        // each segment node in the buffer is one line in the editor
-        // so we can get the absolute_y iterate over buffer and find the exact line where we
-        // currently at.
+        // each segment node in the buffer is one line in the editor, so we can get the
+        // absolute_y and find the exact line where we currently at.
         //
-        // each segment node have also an offset fields, which should point to the place where the
+        // each segment node have also an offset fields, which points to the place where the
         // line starts. so in order to find the exact place we need to add x to line offset
         //
-        // to handle inserts, we will use lines for now, as it seems like less complicated and will
-        // be slightly more efficient. but potential bottleneck for this approach are small one
-        // character updates, which will led to saving whole line.
-        //
-        // or we can do both actually. keep tracking buffer of character that are typed and if
-        // changes are made in one place, eg without jumping in many places in the line.
-        // and we can also track a buffered line that will contain all updates to efficently redraw
-        // it. and if it changed in many places we will need to insert the whole line, otherwise,
-        // insert only buffered sequence of characters.
-        //
-        // to display stdout efficently, we may consider to get current cursor, remove current line
-        // and write only buffered line. so buffered line should keep all changed that we typed,
-        // and we also need to keep character sequence to later determine what we should insert
-        // into the piece table.
-        //
-        // both variants should react to \n to make an insertion.
-        // when we handle a new line we need to write it as well, so when new line is created, we
-        // need to shift front item from the buffer, and rearange buffer,
+        // piece table is the source of truth, the segment only keeps a copy of visible lines.
+        // changes within a line are mirrored into the segment node and only this line is
+        // redrawn, when lines are created or joined the segment is loaded again.
+
+        let current_line = match self.current_line.clone() {
+            Ok(ln) => ln,
+            Err(_) => return,
+        };
+        // the cursor column counts graphemes, edits work on bytes of the line
+        let col = self.cursor.col();
+        let idx = columns::byte_of(&current_line.value, col);
+        let offset = current_line.offset + idx;
 
-        let mut current_line = self.current_line.clone().unwrap();
-        let current_line_len = self.get_ln_len(&current_line.value);
-        // handle changes in line
         match item {
             codes::BACKSPACE => {
-                if self.cursor.absolute_y == 1 && self.cursor.x == 1 {
+                if idx == 0 {
+                    if current_line.line_number > 1 {
+                        self.join_line(current_line.line_number - 1);
+                    }
                     return;
                 }
 
-                if self.cursor.relative_y == 1 && self.cursor.x == 1 {
-                    return;
+                let prev = columns::byte_of(&current_line.value, col - 1);
+                self.delete_text(current_line.offset + prev, idx - prev);
+
+                let mut updated_ln = current_line.value.clone();
+                updated_ln.replace_range(prev..idx, "");
+                self.cursor.set_col(columns::col_of(&updated_ln, prev));
+                self.replace_cur_line(current_line.line_number, &updated_ln);
+            }
+            codes::DELETE => match current_line.value[idx..].chars().next() {
+                None => {}
+                Some(codes::RETURN) => self.join_line(current_line.line_number),
+                Some(_) => {
+                    let next = columns::byte_of(&current_line.value, col + 1);
+                    self.delete_text(offset, next - idx);
+
+                    let mut updated_ln = current_line.value.clone();
+                    updated_ln.replace_range(idx..next, "");
+                    self.replace_cur_line(current_line.line_number, &updated_ln);
                 }
+            },
+            codes::RETURN => {
+                self.insert_text("\n", offset);
+                self.reload_segment();
+                self.cursor.set_col(1);
+                self.motion(Motions::Down);
+            }
+            c => {
+                self.insert_text(&c.to_string(), offset);
 
-                if (self.cursor.x as i16 - 2) < 0 {
-                    // end of the line
-                    logger::log_to_file(&format!("{:?} \n  {:?}", &self.cursor, &current_line));
-                    let prev_line = match self.segment.get_line(current_line.line_number - 1) {
-                        Ok(v) => v,
-                        Err(_) => return,
-                    };
+                // combining marks join the grapheme before them, so the column is found again
+                let mut updated_ln = current_line.value.clone();
+                updated_ln.insert(idx, c);
+                self.cursor
+                    .set_col(columns::col_of(&updated_ln, idx + c.len_utf8()));
+                self.replace_cur_line(current_line.line_number, &updated_ln);
+            }
+        }
+    }
 
-                    let mut merged_val = String::new();
-                    merged_val.push_str(&prev_line.value.clone());
-                    merged_val.pop();
-                    merged_val.push_str(&current_line.value.clone());
+    /// removes line break at the end of the ln, and places the cursor where lines were joined
+    fn join_line(&mut self, ln: usize) {
+        let line = match self.segment.get_line(ln) {
+            Ok(l) => l.clone(),
+            Err(_) => match self.data.get_lines(ln, ln).front() {
+                Some(l) => l.clone(),
+                None => return,
+            },
+        };
 
-                    self.segment
-                        .update_at(current_line.line_number - 1, &merged_val);
+        let content = match line.value.strip_suffix(codes::RETURN) {
+            Some(c) => c,
+            None => return,
+        };
+        let col = self.get_ln_len(content);
 
-                    self.cursor.move_up();
-                    self.stdio.update_line(&merged_val, &self.cursor);
+        self.delete_text(line.offset + content.len(), 1);
+        self.reload_segment();
 
-                    return;
-                }
+        if self.cursor.absolute_y > ln {
+            self.motion(Motions::Up);
+        }
+        self.cursor.set_col(col);
+        self.update_cur_line();
+        self.display_motion(self.cursor.col());
+    }
 
-                let updated_ln = &mut current_line.value;
-                updated_ln.remove((self.cursor.x - 2).into());
+    fn replace_cur_line(&mut self, ln: usize, value: &str) {
+        let layout = self.layout();
+        let rows = self
+            .segment
+            .get_line(ln)
+            .map_or(1, |n| layout.rows(&n.value).len());
+
+        self.segment.update_at(ln, value);
+        self.set_curr_line_value(value);
+        if self.highlight.is_some() {
+            // matches may appear or disappear anywhere on the screen
+            self.display_segment();
+        } else if layout.rows(value).len() != rows {
+            // lines below a wrapped line move to other rows
+            self.reload_segment();
+        } else {
+            self.display_updated();
+        }
+        self.display_motion(self.cursor.col());
+    }
 
-                self.segment
-                    .update_at(current_line.line_number, &updated_ln);
-                self.stdio.update_line(&updated_ln, &self.cursor);
-                self.set_curr_line_value(&updated_ln);
-                self.motion(Motions::Left);
+    /// draws lines changed since they were drawn last, only they are composed again
+    fn display_updated(&mut self) {
+        let layout = self.layout();
+        let height = usize::from(self.stdio.text_height());
+        let pos = self.position();
+
+        let mut row = 0;
+        let mut updated = Vec::new();
+        for node in self.segment.nodes.iter_mut() {
+            if node.updated {
+                node.updated = false;
+                updated.push((row, node.clone()));
             }
-            codes::RETURN => {
-                // need to create a function that regenerates segment optimally eg only few nodes
-                if self.cursor.x == 1 {
-                    self.segment
-                        .insert_at(current_line.line_number, &String::from("\n"));
-                } else if self.cursor.x == current_line_len {
-                    self.segment
-                        .insert_at(current_line.line_number + 1, &String::from("\n"));
-                } else {
-                    let pt1 = &current_line.value[..(self.cursor.x - 1).into()];
-                    let pt2 = &current_line.value[(self.cursor.x - 1).into()..];
-
-                    self.segment
-                        .update_at(current_line.line_number, &format!("{}{}", pt1, "\n"));
-                    self.segment
-                        .insert_at(current_line.line_number + 1, &String::from(pt2));
-
-                    self.data
-                        .insert(&format!("{}{}{}", pt1, "\n", pt2), current_line.offset);
-                    self.cursor.x = 1;
-                    self.cursor.vertical_x = 1;
-                }
+            row += layout.rows(&node.value).len();
+        }
 
-                self.display_segment();
-                logger::log_to_file(&format!("{:?} \n\n {:?}", self.data.add, self.data.pieces));
-                self.motion(Motions::Down);
-                return;
+        for (row, node) in updated {
+            let parts = self.compose(&layout, &node, &[], pos);
+            for (i, part) in parts.iter().enumerate().filter(|(i, _)| row + i < height) {
+                self.stdio.update_line((row + i + 1) as u16, part);
             }
-            c => {
-                let pt1 = &current_line.value[..(self.cursor.x - 1).into()];
-                let pt2 = &current_line.value[(self.cursor.x - 1).into()..];
+        }
+    }
+
+    /// how lines are split into rows, the text is as wide as the screen without the gutter
+    fn layout(&self) -> Layout {
+        let width = usize::from(self.stdio.terminal_size.0);
+        Layout {
+            wrap: self.options.wrap,
+            width: width
+                .saturating_sub(usize::from(self.gutter_width()))
+                .max(1),
+            tabstop: self.options.tabstop,
+            linebreak: self.options.linebreak,
+            showbreak: self.options.showbreak.clone(),
+        }
+    }
+
+    /// row of the screen where the line starts counted from 0, None when it's not loaded
+    fn line_row(&self, layout: &Layout, ln: usize) -> Option<usize> {
+        let mut row = 0;
+        for node in self.segment.nodes.iter() {
+            if node.line_number == ln {
+                return Some(row);
+            }
+            row += layout.rows(&node.value).len();
+        }
+        None
+    }
+
+    /// columns taken by line numbers and the space after them, 0 when they are not shown
+    fn gutter_width(&self) -> u16 {
+        if !self.options.number && !self.options.relativenumber {
+            return 0;
+        }
+
+        let digits = self.data.line_count().to_string().len();
+        digits.max(3) as u16 + 1
+    }
+
+    /// number of the line shown before it, None when numbers are not shown
+    fn gutter(&self, ln: usize) -> Option<(String, Style)> {
+        let width = usize::from(self.gutter_width()).checked_sub(1)?;
+        let current = self.cursor.absolute_y;
+
+        let text = match (self.options.number, self.options.relativenumber) {
+            // the cursor line has it's own number on the left, others count from it
+            (true, true) if ln == current => format!("{:<width$} ", ln),
+            (_, true) => format!("{:>width$} ", ln.abs_diff(current)),
+            _ => format!("{:>width$} ", ln),
+        };
+        let style = if ln == current {
+            Style::CurrentLineNr
+        } else {
+            Style::LineNr
+        };
+
+        Some((text, style))
+    }
+
+    /// draws numbers of visible lines again, they change with the cursor line
+    fn display_gutter(&mut self) {
+        let layout = self.layout();
+        let height = usize::from(self.stdio.text_height());
+
+        let mut row = 0;
+        let mut numbers = Vec::new();
+        for node in self.segment.nodes.iter() {
+            if row < height {
+                numbers.extend(self.gutter(node.line_number).map(|g| (row, g)));
+            }
+            row += layout.rows(&node.value).len();
+        }
+
+        for (row, (text, style)) in numbers {
+            self.stdio.display_at(1, (row + 1) as u16, &text, style);
+        }
+    }
+
+    /// loads visible lines from the piece table again, keeping the same first line
+    fn reload_segment(&mut self) {
+        let first = self.segment.front().map_or(1, |n| n.line_number);
+        self.load_segment(first);
+        self.display_segment();
+    }
+
+    /// loads lines from the first one that start on the screen
+    fn load_segment(&mut self, first: usize) {
+        let last = first + usize::from(self.stdio.text_height()) - 1;
+        self.segment = self.data.get_lines(first, last);
+        self.fit_segment();
+    }
+
+    /// wrapped lines take many rows, lines that start below the screen are left out. the last
+    /// line may still be seen only partly.
+    fn fit_segment(&mut self) {
+        let layout = self.layout();
+        let height = usize::from(self.stdio.text_height());
+
+        let mut used = 0;
+        let visible = self
+            .segment
+            .nodes
+            .iter()
+            .take_while(|n| {
+                let starts_on_screen = used < height;
+                used += layout.rows(&n.value).len();
+                starts_on_screen
+            })
+            .count();
+        self.segment.nodes.truncate(visible.max(1));
+    }
+
+    /// scrolls until the cursor line is loaded and the row of the column in it is on the
+    /// screen, returns true when the segment changed. a line taller than the screen stays at
+    /// the top, so it's rows below the screen are not seen.
+    fn scroll_to_cursor(&mut self, col: usize) -> bool {
+        let ln = self.cursor.absolute_y;
+        let height = usize::from(self.stdio.text_height());
+        let layout = self.layout();
+        let rows = |line: &str| layout.rows(line).len();
+        let row = match self.current_line.as_ref() {
+            Ok(node) => {
+                let idx = columns::byte_of(&node.value, col);
+                layout.row_of(&layout.rows(&node.value), idx)
+            }
+            Err(_) => 0,
+        };
 
-                let updated_ln = format!("{}{}{}", pt1, c, pt2);
-                self.stdio.update_line(&updated_ln, &self.cursor);
-                self.segment
-                    .update_at(current_line.line_number, &updated_ln);
-                self.set_curr_line_value(&updated_ln);
-                self.cursor.move_right();
+        let mut scrolled = false;
+        while let Some(first) = self.segment.front().map(|n| n.line_number) {
+            if first > ln {
+                self.data.prev_line(&mut self.segment, height, rows);
+            } else if first < ln && self.line_row(&layout, ln).is_none_or(|r| r + row >= height) {
+                self.data.next_line(&mut self.segment, height, rows);
+            } else {
+                break;
             }
+
+            if self.segment.front().map(|n| n.line_number) == Some(first) {
+                break;
+            }
+            scrolled = true;
         }
+
+        scrolled
     }
 
-    fn set_curr_line_value(&mut self, new_v: &String) {
+    fn set_curr_line_value(&mut self, new_v: &str) {
         if let Ok(ref mut l) = self.current_line {
             l.value = new_v.to_string();
         }
     }
 
     fn update_cur_line(&mut self) {
-        let new_line = self
-            .segment
-            .get_line(usize::from(self.cursor.absolute_y))
-            .cloned()
-            .unwrap();
+        let ln = self.cursor.absolute_y;
+        // the line may be off the screen until the view scrolls to it
+        let new_line = match self.segment.get_line(ln) {
+            Ok(line) => line.clone(),
+            Err(_) => self.data.get_lines(ln, ln).front().cloned().unwrap(),
+        };
 
+        if let Some(x) = self.cursor.screen_x {
+            self.cursor.x = columns::col_at(&new_line.value, x, self.options.tabstop);
+        }
         let new_ln_len = self.get_ln_len(&new_line.value);
 
         if new_ln_len < self.cursor.x {
-            self.cursor.vertical_x = new_ln_len - 1;
+            self.cursor.vertical_x = (new_ln_len - 1).max(1);
         } else {
             self.cursor.vertical_x = self.cursor.x;
         }
@@ -260,26 +1223,164 @@ impl Buffer {
         self.current_line = Ok(new_line);
     }
 
-    fn get_ln_len(&mut self, ln: &String) -> u16 {
-        let mut node_len = ln.len() as u16;
-        let tabs = ln.matches("\t").count();
+    /// screen column of the cursor when it starts to move up or down, the moves after it
+    /// keep the first one
+    fn keep_screen_x(&mut self) {
+        if self.cursor.screen_x.is_none() {
+            let line = self.current_line.as_ref().map_or("", |n| n.value.as_str());
+            let col = self.cursor.col();
+            self.cursor.screen_x = Some(columns::screen_col(line, col, self.options.tabstop));
+        }
+    }
+
+    /// last column the cursor can be placed at, one after the last grapheme in the line
+    fn get_ln_len(&mut self, ln: &str) -> usize {
+        columns::count(ln) + 1
+    }
 
-        if tabs > 0 {
-            node_len += tabs as u16 * constants::TABULATION_COUNT;
+    /// screen position of the column in the current line, wide items and tabs take more space,
+    /// the text starts after the gutter and wrapped lines continue on the rows below. the
+    /// cursor row is kept in relative_y.
+    fn screen_pos(&mut self, col: usize) -> (usize, usize) {
+        let layout = self.layout();
+        let (x, row) = match self.current_line.as_ref() {
+            Ok(node) => {
+                let rows = layout.rows(&node.value);
+                let idx = columns::byte_of(&node.value, col);
+                (
+                    layout.x_of(&node.value, &rows, idx),
+                    layout.row_of(&rows, idx),
+                )
+            }
+            Err(_) => (col, 0),
+        };
+
+        let first = self.line_row(&layout, self.cursor.absolute_y).unwrap_or(0);
+        self.cursor.relative_y = first + row + 1;
+        self.cursor.fit(usize::from(self.stdio.text_height()));
+        (x + usize::from(self.gutter_width()), self.cursor.relative_y)
+    }
+
+    fn display_motion(&mut self, col: usize) {
+        if self.scroll_to_cursor(col) {
+            self.display_segment();
+        }
+        if self.gutter_width() > 0 {
+            self.display_gutter();
         }
 
-        node_len
+        let pos = self.screen_pos(col);
+        self.stdio.goto_line(pos);
     }
 
-    fn display_motion(&mut self, x: u16) {
-        self.stdio
-            .goto_line((x, self.cursor.relative_y, self.cursor.absolute_y));
+    /// rows of the screen the line is shown on, made of styled parts. tabs are drawn as spaces
+    /// up to the next tab stop so the text agrees with screen columns of the cursor. the line
+    /// number is before the first row, rows that continue the line start with showbreak.
+    fn compose(
+        &self,
+        layout: &Layout,
+        node: &SegmentNode,
+        matches: &[(usize, usize)],
+        pos: (usize, usize),
+    ) -> Vec<Vec<(String, Style)>> {
+        // line break is not printable, so it's left out of highlights
+        let content_len = node.value.trim_end_matches('\n').len();
+        let mut marks: Vec<(usize, usize, Style)> = Vec::new();
+
+        let columns = self
+            .selection
+            .as_ref()
+            .and_then(|s| s.columns(pos, node.line_number, &node.value));
+        if let Some((from, to)) = columns {
+            let to = to.min(content_len).max(from);
+            marks.push((from, to, Style::Selection));
+        }
+
+        for (offset, len) in matches.iter() {
+            if *offset < node.offset || *offset >= node.offset + content_len {
+                continue;
+            }
+
+            let from = offset - node.offset;
+            let to = (from + len).min(content_len);
+            if marks.iter().all(|m| to <= m.0 || from >= m.1) {
+                marks.push((from, to, Style::Match));
+            }
+        }
+
+        marks.sort_by_key(|m| m.0);
+
+        // text between marks is normal, the parts are split again where rows start
+        let mut parts = Vec::new();
+        let mut done = 0;
+        for (from, to, style) in marks {
+            parts.push((done, from, Style::Normal));
+            parts.push((from, to, style));
+            done = to;
+        }
+        parts.push((done, content_len, Style::Normal));
+
+        let gutter = self.gutter(node.line_number);
+        let starts = layout.rows(&node.value);
+        let mut x = 0;
+        let mut rows = Vec::new();
+        for (i, start) in starts.iter().enumerate() {
+            let end = starts.get(i + 1).copied().unwrap_or(content_len);
+            let mut row = Vec::new();
+
+            match gutter.as_ref() {
+                Some(number) if i == 0 => row.push(number.clone()),
+                Some(_) => row.push((" ".repeat(usize::from(self.gutter_width())), Style::Normal)),
+                None => {}
+            }
+            if i > 0 && !layout.showbreak.is_empty() {
+                row.push((layout.showbreak.clone(), Style::NonText));
+            }
+
+            for (from, to, style) in parts.iter() {
+                let (from, to) = ((*from).max(*start), (*to).min(end));
+                if from < to {
+                    let text = columns::expand_tabs(&node.value[from..to], &mut x, layout.tabstop);
+                    row.push((text, *style));
+                }
+            }
+            rows.push(row);
+        }
+
+        rows
     }
 
+    /// draws all visible lines, the last one is cut at the bottom of the screen
     fn display_segment(&mut self) {
-        let text = self.segment.construct_segment();
-        logger::log_to_file(&format!("{}", &text));
-        self.stdio
-            .display_segment(text, (self.cursor.relative_y, self.cursor.x));
+        for node in self.segment.nodes.iter_mut() {
+            node.updated = false;
+        }
+
+        let layout = self.layout();
+        let height = usize::from(self.stdio.text_height());
+        let pos = self.position();
+        let matches = match (self.highlight.as_ref(), self.segment.front()) {
+            (Some(pattern), Some(first)) => {
+                let last = self.segment.back().unwrap();
+                let to = last.offset + last.value.len();
+                search::find_in(&self.data, pattern, first.offset, to)
+            }
+            _ => Vec::new(),
+        };
+
+        let mut rows = Vec::new();
+        for node in self.segment.nodes.iter() {
+            rows.extend(self.compose(&layout, node, &matches, pos));
+        }
+        rows.truncate(height);
+
+        let mut text = Vec::new();
+        for row in rows {
+            text.extend(row);
+            text.push(("\n".to_string(), Style::Normal));
+        }
+
+        let pos = self.screen_pos(self.cursor.col());
+        self.stdio.display_segment(&text, pos);
     }
 }
@@ -0,0 +1,115 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// conversions between byte indexes in a line, cursor columns and screen columns.
+// the cursor moves over grapheme clusters, so a letter with combining marks or an emoji
+// made of many chars is one column. screen columns count wide chars twice and tabs up to
+// the next tab stop. columns start from 1.
+
+fn content(line: &str) -> &str {
+    line.strip_suffix('\n').unwrap_or(line)
+}
+
+/// count of graphemes in the line, the line break is not counted
+pub fn count(line: &str) -> usize {
+    content(line).graphemes(true).count()
+}
+
+/// byte index in the line where the grapheme at the column starts, columns after the last
+/// grapheme are at the end of the content
+pub fn byte_of(line: &str, col: usize) -> usize {
+    let content = content(line);
+    content
+        .grapheme_indices(true)
+        .nth(col.saturating_sub(1))
+        .map_or(content.len(), |(idx, _)| idx)
+}
+
+/// column of the grapheme the byte index is in, indexes after the content are one column
+/// after the last grapheme
+pub fn col_of(line: &str, idx: usize) -> usize {
+    let content = content(line);
+    if idx >= content.len() {
+        return count(line) + 1;
+    }
+
+    content
+        .grapheme_indices(true)
+        .take_while(|(start, _)| *start <= idx)
+        .count()
+}
+
+/// width of the grapheme on the screen when it starts at the screen column counted from 0,
+/// tabs reach the next tab stop
+pub fn width(grapheme: &str, at: usize, tabstop: usize) -> usize {
+    if grapheme == "\t" {
+        let tabstop = tabstop.max(1);
+        tabstop - at % tabstop
+    } else {
+        grapheme.width()
+    }
+}
+
+/// screen column where the grapheme at the column starts
+pub fn screen_col(line: &str, col: usize, tabstop: usize) -> usize {
+    content(line)
+        .graphemes(true)
+        .take(col.saturating_sub(1))
+        .fold(0, |x, g| x + width(g, x, tabstop))
+        + 1
+}
+
+/// column of the grapheme the screen column is in, screen columns after the content are
+/// columns after the last grapheme
+pub fn col_at(line: &str, x: usize, tabstop: usize) -> usize {
+    let mut end = 0;
+    for (i, g) in content(line).graphemes(true).enumerate() {
+        end += width(g, end, tabstop);
+        if end >= x {
+            return i + 1;
+        }
+    }
+
+    count(line) + 1 + x.saturating_sub(end + 1)
+}
+
+/// the text with tabs turned into spaces up to the next tab stop. x is the screen column the
+/// text starts at, counted from 0, it's moved to where the text ends. line breaks start from
+/// column 0 again.
+pub fn expand_tabs(text: &str, x: &mut usize, tabstop: usize) -> String {
+    let mut res = String::with_capacity(text.len());
+    for g in text.graphemes(true) {
+        match g {
+            "\t" => {
+                let w = width(g, *x, tabstop);
+                res.push_str(&" ".repeat(w));
+                *x += w;
+            }
+            "\n" | "\r\n" => {
+                res.push_str(g);
+                *x = 0;
+            }
+            _ => {
+                res.push_str(g);
+                *x += width(g, *x, tabstop);
+            }
+        }
+    }
+
+    res
+}
+
+/// white space that indents by the screen width, tabs fill it as far as they can unless only
+/// spaces are wanted
+pub fn indent(width: usize, tabstop: usize, spaces: bool) -> String {
+    if spaces {
+        return " ".repeat(width);
+    }
+
+    let tabstop = tabstop.max(1);
+    format!(
+        "{}{}",
+        "\t".repeat(width / tabstop),
+        " ".repeat(width % tabstop)
+    )
+}
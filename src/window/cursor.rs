@@ -1,38 +1,48 @@
 #[derive(Clone, Debug)]
 pub struct Cursor {
-    pub x: u16,
-    pub relative_y: u16,
-    pub absolute_y: u16,
-    pub vertical_x: u16,
+    pub x: usize,
+    /// row of the screen the cursor is on, wrapped lines above it take many rows
+    pub relative_y: usize,
+    pub absolute_y: usize,
+    pub vertical_x: usize,
+    /// screen column kept while moving up and down, tabs and wide items before it take more
+    /// than one column so it's at other columns on other lines. any other move forgets it.
+    pub screen_x: Option<usize>,
 }
 
 impl Cursor {
-    pub fn set_x(&mut self, v: u16) {
+    pub fn set_x(&mut self, v: usize) {
         self.x = v;
+        self.screen_x = None;
     }
-    pub fn move_up(&mut self) {
-        if self.relative_y > 1 {
-            self.relative_y -= 1;
-        }
 
-        self.absolute_y -= 1;
+    /// x is the column we would like to stay at while moving vertically, vertical_x is where the
+    /// cursor ended up on the current line, so the real column is the smallest of them
+    pub fn col(&self) -> usize {
+        self.x.min(self.vertical_x)
     }
 
-    pub fn move_down(&mut self, max_y: u16) {
-        self.absolute_y += 1;
-        if self.relative_y + 1 >= max_y {
-            return;
-        }
-        self.relative_y += 1;
+    pub fn set_col(&mut self, v: usize) {
+        self.x = v;
+        self.vertical_x = v;
+        self.screen_x = None;
+    }
+
+    /// keeps the cursor within the rows of a screen of the height, the line under it stays
+    /// the same so the view scrolls when it's cut off at the bottom
+    pub fn fit(&mut self, height: usize) {
+        self.relative_y = self.relative_y.clamp(1, height.max(1));
     }
 
     pub fn move_right(&mut self) {
         self.x += 1;
+        self.screen_x = None;
     }
 
     pub fn move_left(&mut self) {
         if self.x > 1 {
             self.x -= 1;
         }
+        self.screen_x = None;
     }
 }
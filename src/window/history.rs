@@ -0,0 +1,128 @@
+use crate::window::piece_table::Piece;
+
+/// single change of the piece list. as the add buffer is append only, pieces that were
+/// inserted or deleted stay valid, so a change can be reverted without copying text.
+#[derive(Clone, Debug)]
+pub enum Change {
+    Insert { offset: usize, pieces: Vec<Piece> },
+    Delete { offset: usize, pieces: Vec<Piece> },
+}
+
+impl Change {
+    pub fn len(&self) -> usize {
+        match self {
+            Change::Insert { pieces, .. } | Change::Delete { pieces, .. } => {
+                pieces.iter().map(|p| p.length()).sum()
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// one undo step, keeps cursor (line, column) before and after the changes
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub changes: Vec<Change>,
+    pub cursor_before: (usize, usize),
+    pub cursor_after: (usize, usize),
+    /// steps are numbered in the order they are made, from 1
+    id: usize,
+}
+
+#[derive(Debug)]
+pub struct History {
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    open: Option<Step>,
+    /// count of steps made so far
+    made: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            open: None,
+            made: 0,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open.is_some()
+    }
+
+    /// starts a step, all changes pushed until end are undone at once
+    pub fn begin(&mut self, cursor: (usize, usize)) {
+        if self.open.is_some() {
+            return;
+        }
+
+        self.open = Some(Step {
+            changes: Vec::new(),
+            cursor_before: cursor,
+            cursor_after: cursor,
+            id: 0,
+        });
+    }
+
+    pub fn push(&mut self, change: Change) {
+        if change.is_empty() {
+            return;
+        }
+
+        if let Some(step) = self.open.as_mut() {
+            step.changes.push(change);
+        }
+    }
+
+    /// closes the step, steps without changes are dropped
+    pub fn end(&mut self, cursor: (usize, usize)) {
+        let mut step = match self.open.take() {
+            Some(s) => s,
+            None => return,
+        };
+
+        if step.changes.is_empty() {
+            return;
+        }
+
+        self.made += 1;
+        step.cursor_after = cursor;
+        step.id = self.made;
+        self.undo.push(step);
+        self.redo.clear();
+    }
+
+    /// number of the last step that is not undone, 0 before the first one. numbers are not
+    /// reused, so the text is the same whenever the position is. changes of an open step have
+    /// no position yet.
+    pub fn position(&self) -> Option<usize> {
+        if self.open.as_ref().is_some_and(|s| !s.changes.is_empty()) {
+            return None;
+        }
+
+        Some(self.undo.last().map_or(0, |s| s.id))
+    }
+
+    pub fn undo(&mut self) -> Option<Step> {
+        let step = self.undo.pop()?;
+        self.redo.push(step.clone());
+        Some(step)
+    }
+
+    pub fn redo(&mut self) -> Option<Step> {
+        let step = self.redo.pop()?;
+        self.undo.push(step.clone());
+        Some(step)
+    }
+}
@@ -1,4 +1,10 @@
 pub mod buffer;
+pub mod columns;
 pub mod cursor;
+pub mod history;
 pub mod piece_table;
+pub mod piece_tree;
+pub mod search;
 pub mod segment;
+pub mod selection;
+pub mod wrap;
@@ -1,197 +1,412 @@
+use crate::window::piece_tree::PieceTree;
 use crate::window::segment::Segment;
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 enum Source {
     Original,
     Add,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Piece {
     source: Source,
     offset: usize,
     length: usize,
+    line_breaks: usize,
+}
+
+impl Piece {
+    pub fn length(&self) -> usize {
+        self.length
+    }
+
+    pub fn line_breaks(&self) -> usize {
+        self.line_breaks
+    }
+
+    /// divides piece into items before and after at, breaks are positions of line breaks in the
+    /// buffer the piece points to
+    fn split(&self, at: usize, breaks: &[usize]) -> (Piece, Piece) {
+        let before = count_breaks(breaks, self.offset, self.offset + at);
+
+        (
+            Piece {
+                source: self.source,
+                offset: self.offset,
+                length: at,
+                line_breaks: before,
+            },
+            Piece {
+                source: self.source,
+                offset: self.offset + at,
+                length: self.length - at,
+                line_breaks: self.line_breaks - before,
+            },
+        )
+    }
 }
 
+/// count of line breaks within from..to, breaks are sorted so it's 2 binary searches
+fn count_breaks(breaks: &[usize], from: usize, to: usize) -> usize {
+    breaks.partition_point(|&b| b < to) - breaks.partition_point(|&b| b < from)
+}
+
+fn line_breaks_of(text: &str, offset: usize) -> impl Iterator<Item = usize> + '_ {
+    text.bytes()
+        .enumerate()
+        .filter(|(_, b)| *b == b'\n')
+        .map(move |(i, _)| offset + i)
+}
+
+/// pieces are kept in a balanced tree, where each node knows length and count of line breaks
+/// of its subtree. together with positions of line breaks in both buffers it gives
+/// O(log n) lookups from offset to line and back.
 #[derive(Debug)]
 pub struct PieceTable {
     pub original: String,
     pub add: String,
-    pub pieces: Vec<Piece>,
+    pub pieces: PieceTree,
+    original_breaks: Vec<usize>,
+    add_breaks: Vec<usize>,
 }
 
 impl PieceTable {
-    pub fn new(buffer: &String) -> PieceTable {
-        let pieces = vec![Piece {
+    pub fn new(buffer: &str) -> PieceTable {
+        let original_breaks: Vec<usize> = line_breaks_of(buffer, 0).collect();
+        let mut pieces = PieceTree::new();
+        let piece = Piece {
             source: Source::Original,
             length: buffer.len(),
             offset: 0,
-        }];
+            line_breaks: original_breaks.len(),
+        };
+
+        pieces.insert(0, piece, &|_: &Piece, _| unreachable!());
+
         PieceTable {
             original: buffer.to_string(),
             add: String::new(),
             pieces,
+            original_breaks,
+            add_breaks: Vec::new(),
         }
     }
 
-    /// create 3 pieces
-    /// 1 - points to items before line
-    /// 3 - poinst to items after line
-    /// 2 - points to items in Add buffer
-    /// append items to the Add buffer
-    /// remove old piece
-    /// to get offset we can store offset and length of the segment node
-    /// if we have the offset we can then just make a simple calculations to get the offset
-    /// parameter.
-    pub fn insert(&mut self, items: &String, offset: usize) {
-        if items.is_empty() {
+    pub fn len(&self) -> usize {
+        self.pieces.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pieces.is_empty()
+    }
+
+    /// appends items to the Add buffer and places a piece that points to them at the document
+    /// offset. the piece that contains offset is divided into 2 pieces, one points to items
+    /// before the offset and the other to items after it, both keep their source buffer.
+    /// if several items are added in a row, the inserted items are combined into one piece.
+    pub fn insert(&mut self, items: &str, offset: usize) {
+        if items.is_empty() || offset > self.len() {
             return;
         }
 
-        let mut found = false;
-        let mut new_pieces: Vec<Piece> = Vec::new();
-        let mut found_idx = 0;
+        let add_offset = self.add.len();
+        self.add_breaks.extend(line_breaks_of(items, add_offset));
+        self.add.push_str(items);
+
+        let mut piece = Piece {
+            source: Source::Add,
+            offset: add_offset,
+            length: items.len(),
+            line_breaks: count_breaks(&self.add_breaks, add_offset, add_offset + items.len()),
+        };
+        let mut offset = offset;
 
-        for (i, piece) in self.pieces.iter().enumerate() {
-            let is_within = piece.offset <= offset && offset < piece.offset + piece.length;
+        let prev = offset
+            .checked_sub(1)
+            .and_then(|o| self.pieces.find(o))
+            .map(|(p, start, _)| (p.clone(), start));
 
-            if !is_within {
-                continue;
+        if let Some((prev, prev_start)) = prev {
+            let is_adjacent = prev_start + prev.length == offset;
+            if matches!(prev.source, Source::Add)
+                && is_adjacent
+                && prev.offset + prev.length == add_offset
+            {
+                piece.offset = prev.offset;
+                piece.length += prev.length;
+                piece.line_breaks += prev.line_breaks;
+                offset = prev_start;
+                self.delete(prev_start, prev.length);
             }
+        }
 
-            found = true;
-            found_idx = i;
+        let (original, add) = (&self.original_breaks, &self.add_breaks);
+        self.pieces
+            .insert(offset, piece, &|p: &Piece, at| match p.source {
+                Source::Original => p.split(at, original),
+                Source::Add => p.split(at, add),
+            });
+    }
 
-            if offset > piece.offset {
-                let before_piece = Piece {
-                    source: Source::Original,
-                    length: offset - piece.offset,
-                    offset: piece.offset,
-                };
+    /// removes len items starting at the document offset
+    /// pieces fully covered by the range are dropped, if the range starts or ends within a piece
+    /// it's divided, so only items outside of the range are kept.
+    pub fn delete(&mut self, offset: usize, len: usize) {
+        let (original, add) = (&self.original_breaks, &self.add_breaks);
+        self.pieces
+            .remove(offset, len, &|p: &Piece, at| match p.source {
+                Source::Original => p.split(at, original),
+                Source::Add => p.split(at, add),
+            });
+    }
 
-                new_pieces.push(before_piece);
-            }
+    /// copies of pieces that describe items in the range, first and last pieces are trimmed to
+    /// the range bounds. as buffers are never modified, pieces stay valid and can be inserted
+    /// back later, e.g. by undo.
+    pub fn pieces_at(&self, offset: usize, len: usize) -> Vec<Piece> {
+        let end = offset + len;
+        let mut res = Vec::new();
 
-            let new_piece = Piece {
-                offset: self.add.len(),
-                length: items.len(),
-                source: Source::Add,
-            };
-
-            new_pieces.push(new_piece);
-            self.add.push_str(&items);
-            if offset < piece.offset + piece.length {
-                let after_piece = Piece {
-                    source: piece.source.clone(),
-                    offset,
-                    length: piece.offset + piece.length - offset,
-                };
-                new_pieces.push(after_piece);
+        for (piece, piece_start) in self.pieces.iter_from(offset) {
+            if piece_start >= end {
+                break;
             }
 
-            break;
+            let from = offset.max(piece_start) - piece_start;
+            let to = end.min(piece_start + piece.length) - piece_start;
+            let piece_offset = piece.offset + from;
+            res.push(Piece {
+                source: piece.source,
+                offset: piece_offset,
+                length: to - from,
+                line_breaks: count_breaks(self.breaks(piece), piece_offset, piece.offset + to),
+            });
         }
 
-        if found {
-            self.pieces.splice(found_idx..=found_idx, new_pieces);
-        } else if offset == self.original.len() + self.add.len() {
-            let add_offset = self.add.len();
-            self.add.push_str(&items);
-            let new_piece = Piece {
-                source: Source::Add,
-                offset: add_offset,
-                length: items.len(),
-            };
-            self.pieces.push(new_piece);
+        res
+    }
+
+    /// places already existing pieces at the document offset without touching the buffers
+    pub fn insert_pieces(&mut self, offset: usize, pieces: &[Piece]) {
+        let mut offset = offset;
+        let (original, add) = (&self.original_breaks, &self.add_breaks);
+
+        for piece in pieces {
+            self.pieces
+                .insert(offset, piece.clone(), &|p: &Piece, at| match p.source {
+                    Source::Original => p.split(at, original),
+                    Source::Add => p.split(at, add),
+                });
+            offset += piece.length;
         }
     }
 
-    /// creates a ring buffer, and iterates over pieces and it's content.
-    /// in content search for the lines, if the item is equal to \n and a current line is within
-    /// from-to range append it to the ring buffer.
+    pub fn line_count(&self) -> usize {
+        let breaks = self.pieces.line_breaks();
+
+        match self.len().checked_sub(1).and_then(|o| self.item_at(o)) {
+            Some(b'\n') | None => breaks.max(1),
+            Some(_) => breaks + 1,
+        }
+    }
+
+    /// line (starting from 1) that contains the document offset
+    pub fn line_at(&self, offset: usize) -> usize {
+        match self.pieces.find(offset) {
+            Some((piece, piece_start, breaks)) => {
+                let to = piece.offset + (offset - piece_start);
+                breaks + count_breaks(self.breaks(piece), piece.offset, to) + 1
+            }
+            None => self.pieces.line_breaks() + 1,
+        }
+    }
+
+    /// document offset where the line (starting from 1) begins
+    pub fn line_offset(&self, ln: usize) -> Option<usize> {
+        if ln <= 1 {
+            return Some(0);
+        }
+
+        let (piece, piece_start, breaks) = self.pieces.find_line_break(ln - 1)?;
+        let buffer_breaks = self.breaks(piece);
+        let idx = buffer_breaks.partition_point(|&b| b < piece.offset) + (ln - 1 - breaks) - 1;
+
+        Some(piece_start + (buffer_breaks[idx] - piece.offset) + 1)
+    }
+
+    /// lines are found by the offset where the first one starts, and then we iterate over
+    /// pieces and it's content from there.
+    /// if the item is equal to \n and a current line is within from-to range append it to the
+    /// ring buffer.
     /// if last line is not empty and it's withing range but it may not include \n push it to the
     /// res as well.
+    /// node offset points to the place where the line starts.
     pub fn get_lines(&self, from: usize, to: usize) -> Segment {
-        let mut current_line = 1;
         let mut res = Segment::new();
+        let from = from.max(1);
+
+        // empty document still has one line to type in
+        if self.is_empty() {
+            if from <= 1 && to >= 1 {
+                res.new_b(String::new(), 1, 0);
+            }
+            return res;
+        }
+
+        let mut current_offset = match self.line_offset(from) {
+            Some(o) => o,
+            None => return res,
+        };
+        let mut current_line = from;
         let mut line_value = String::new();
-        let mut current_offset = 0;
 
-        for piece in self.pieces.iter() {
-            let segment = match piece.source {
-                Source::Original => &self.original[piece.offset..piece.offset + piece.length],
-                Source::Add => &self.add[piece.offset..piece.offset + piece.length],
-            };
+        'pieces: for (piece, piece_start) in self.pieces.iter_from(current_offset) {
+            let skip = current_offset - piece_start;
+            let text = &self.text(piece)[skip..];
 
-            for char in segment.chars() {
-                current_offset += char.len_utf8();
-                if current_line >= from && to >= current_line {
-                    line_value.push(char);
+            for char in text.chars() {
+                if current_line > to {
+                    break 'pieces;
                 }
 
+                current_offset += char.len_utf8();
+                line_value.push(char);
+
                 if char == '\n' {
-                    if current_line >= from && to >= current_line {
-                        res.new_b(line_value.clone(), current_line, current_offset);
-                        line_value.clear();
-                    }
+                    let line_offset = current_offset - line_value.len();
+                    res.new_b(line_value.clone(), current_line, line_offset);
+                    line_value.clear();
                     current_line += 1;
                 }
-
-                if current_line > to {
-                    break;
-                }
             }
         }
 
-        if !line_value.is_empty() && current_line >= from && current_line <= to {
-            res.new_b(line_value.clone(), current_line, current_offset);
+        if !line_value.is_empty() && current_line <= to {
+            let line_offset = current_offset - line_value.len();
+            res.new_b(line_value.clone(), current_line, line_offset);
         }
 
         res
     }
 
-    pub fn next_line(&self, segment: &mut Segment) {
-        if let Some(last_node) = segment.back() {
-            if let Some(next_line) = self
-                .get_lines(last_node.line_number + 1, last_node.line_number + 1)
-                .front()
-                .cloned()
-            {
-                segment.pop_f(); // Remove the first line
-                segment.add_b(next_line); // Add the new line at the end
+    /// scrolls the segment one line down, the first line goes and lines below are added while
+    /// they start within the height rows. rows tells how many rows of the screen a line takes,
+    /// so the last line may be seen only partly. nothing changes when all of the text below
+    /// is already seen.
+    pub fn next_line(&self, segment: &mut Segment, height: usize, rows: impl Fn(&str) -> usize) {
+        let (first, last) = match (segment.front(), segment.back()) {
+            (Some(f), Some(l)) => (f.line_number, l.line_number),
+            _ => return,
+        };
+        let mut used: usize = segment.nodes.iter().map(|n| rows(&n.value)).sum();
+        if first >= self.line_count() || (used <= height && last >= self.line_count()) {
+            return;
+        }
+
+        if let Some(node) = segment.front() {
+            used -= rows(&node.value);
+        }
+        segment.pop_f();
+
+        while used < height {
+            let ln = segment.back().map_or(first + 1, |n| n.line_number + 1);
+            match self.get_lines(ln, ln).front().cloned() {
+                Some(next_line) => {
+                    used += rows(&next_line.value);
+                    segment.add_b(next_line);
+                }
+                None => break,
             }
         }
     }
 
-    pub fn prev_line(&self, segment: &mut Segment) {
-        if let Some(first_node) = segment.front() {
-            if first_node.line_number <= 1 {
-                return;
+    /// scrolls the segment one line up, lines at the back go when they start below the height
+    /// rows
+    pub fn prev_line(&self, segment: &mut Segment, height: usize, rows: impl Fn(&str) -> usize) {
+        let first = match segment.front() {
+            Some(f) if f.line_number > 1 => f.line_number,
+            _ => return,
+        };
+        let prev_line = match self.get_lines(first - 1, first - 1).front().cloned() {
+            Some(l) => l,
+            None => return,
+        };
+        segment.add_f(prev_line);
+
+        let mut used: usize = segment.nodes.iter().map(|n| rows(&n.value)).sum();
+        while segment.nodes.len() > 1 {
+            let last = rows(&segment.back().unwrap().value);
+            if used - last < height {
+                break;
             }
-            if let Some(prev_line) = self
-                .get_lines(first_node.line_number - 1, first_node.line_number - 1)
-                .front()
-                .cloned()
-            {
-                segment.pop_b();
-                segment.add_f(prev_line);
+            used -= last;
+            segment.pop_b();
+        }
+    }
+
+    /// items of the range as a string
+    pub fn text_at(&self, offset: usize, len: usize) -> String {
+        let end = offset + len;
+        let mut res = String::with_capacity(len);
+
+        for (piece, piece_start) in self.pieces.iter_from(offset) {
+            if piece_start >= end {
+                break;
             }
+
+            let text = self.text(piece);
+            let from = offset.max(piece_start) - piece_start;
+            let to = end.min(piece_start + piece.length) - piece_start;
+            res.push_str(&text[from..to]);
         }
+
+        res
     }
 
-    pub fn get_string(&self) -> String {
-        let mut res = String::new();
-        for piece in &self.pieces {
-            match piece.source {
-                Source::Original => {
-                    res.push_str(&self.original[piece.offset..piece.offset + piece.length]);
-                }
-                Source::Add => {
-                    res.push_str(&self.add[piece.offset..piece.offset + piece.length]);
+    /// text of the document starting at the offset, piece by piece, together with the offset
+    /// where each chunk starts. nothing is copied, so it's cheap to stop early. an offset
+    /// inside of a char starts at the char after it.
+    pub fn chunks_from(&self, offset: usize) -> impl Iterator<Item = (usize, &str)> + '_ {
+        self.pieces
+            .iter_from(offset)
+            .map(move |(piece, piece_start)| {
+                let text = self.text(piece);
+                let mut skip = offset.saturating_sub(piece_start);
+                while !text.is_char_boundary(skip) {
+                    skip += 1;
                 }
-            }
+                (piece_start + skip, &text[skip..])
+            })
+    }
+
+    pub fn get_string(&self) -> String {
+        let mut res = String::with_capacity(self.len());
+        for (piece, _) in self.pieces.iter() {
+            res.push_str(self.text(piece));
         }
 
         res
     }
+
+    fn item_at(&self, offset: usize) -> Option<u8> {
+        let (piece, piece_start, _) = self.pieces.find(offset)?;
+        self.text(piece)
+            .as_bytes()
+            .get(offset - piece_start)
+            .copied()
+    }
+
+    fn text(&self, piece: &Piece) -> &str {
+        match piece.source {
+            Source::Original => &self.original[piece.offset..piece.offset + piece.length],
+            Source::Add => &self.add[piece.offset..piece.offset + piece.length],
+        }
+    }
+
+    fn breaks(&self, piece: &Piece) -> &[usize] {
+        match piece.source {
+            Source::Original => &self.original_breaks,
+            Source::Add => &self.add_breaks,
+        }
+    }
 }
@@ -0,0 +1,287 @@
+use crate::window::piece_table::Piece;
+
+type Link = Option<Box<Node>>;
+
+/// node keeps a piece and sums of lengths and line breaks of the whole subtree, so the piece
+/// that contains a document offset or n-th line break is found by walking down from the root.
+#[derive(Debug)]
+struct Node {
+    piece: Piece,
+    priority: u32,
+    length: usize,
+    line_breaks: usize,
+    left: Link,
+    right: Link,
+}
+
+impl Node {
+    fn new(piece: Piece, priority: u32) -> Box<Node> {
+        Box::new(Node {
+            length: piece.length(),
+            line_breaks: piece.line_breaks(),
+            piece,
+            priority,
+            left: None,
+            right: None,
+        })
+    }
+
+    fn update(&mut self) {
+        self.length = self.piece.length() + length(&self.left) + length(&self.right);
+        self.line_breaks =
+            self.piece.line_breaks() + line_breaks(&self.left) + line_breaks(&self.right);
+    }
+}
+
+fn length(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.length)
+}
+
+fn line_breaks(link: &Link) -> usize {
+    link.as_ref().map_or(0, |n| n.line_breaks)
+}
+
+/// pieces in document order kept in a treap, a binary tree balanced by random priorities,
+/// ordered by position in the document instead of by key. insertion and deletion are done by
+/// splitting the tree at document offsets and merging parts back, both take O(log n).
+#[derive(Debug)]
+pub struct PieceTree {
+    root: Link,
+    seed: u32,
+}
+
+impl Default for PieceTree {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PieceTree {
+    pub fn new() -> PieceTree {
+        PieceTree {
+            root: None,
+            seed: 0x9e37_79b9,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        length(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn line_breaks(&self) -> usize {
+        line_breaks(&self.root)
+    }
+
+    /// places the piece at the document offset, split_piece is called when the offset is in
+    /// the middle of an existing piece and it should return parts before and after it.
+    pub fn insert<F>(&mut self, offset: usize, piece: Piece, split_piece: &F)
+    where
+        F: Fn(&Piece, usize) -> (Piece, Piece),
+    {
+        if piece.length() == 0 {
+            return;
+        }
+
+        let priority = self.next_priority();
+        let root = self.root.take();
+        let (left, right) = self.split(root, offset, split_piece);
+        let left = merge(left, Some(Node::new(piece, priority)));
+        self.root = merge(left, right);
+    }
+
+    /// removes len items starting at the document offset
+    pub fn remove<F>(&mut self, offset: usize, len: usize, split_piece: &F)
+    where
+        F: Fn(&Piece, usize) -> (Piece, Piece),
+    {
+        if len == 0 {
+            return;
+        }
+
+        let root = self.root.take();
+        let (left, rest) = self.split(root, offset, split_piece);
+        let (_, right) = self.split(rest, len, split_piece);
+        self.root = merge(left, right);
+    }
+
+    /// piece that contains the offset, with document offset where it starts and count of line
+    /// breaks before it
+    pub fn find(&self, offset: usize) -> Option<(&Piece, usize, usize)> {
+        let mut link = &self.root;
+        let mut offset = offset;
+        let mut start = 0;
+        let mut breaks = 0;
+
+        while let Some(node) = link {
+            let left_len = length(&node.left);
+
+            if offset < left_len {
+                link = &node.left;
+            } else if offset < left_len + node.piece.length() {
+                return Some((
+                    &node.piece,
+                    start + left_len,
+                    breaks + line_breaks(&node.left),
+                ));
+            } else {
+                offset -= left_len + node.piece.length();
+                start += left_len + node.piece.length();
+                breaks += line_breaks(&node.left) + node.piece.line_breaks();
+                link = &node.right;
+            }
+        }
+
+        None
+    }
+
+    /// piece that contains n-th line break (starting from 1), with document offset where it
+    /// starts and count of line breaks before it
+    pub fn find_line_break(&self, n: usize) -> Option<(&Piece, usize, usize)> {
+        let mut link = &self.root;
+        let mut n = n;
+        let mut start = 0;
+        let mut breaks = 0;
+
+        while let Some(node) = link {
+            let left_breaks = line_breaks(&node.left);
+
+            if n <= left_breaks {
+                link = &node.left;
+            } else if n <= left_breaks + node.piece.line_breaks() {
+                return Some((
+                    &node.piece,
+                    start + length(&node.left),
+                    breaks + left_breaks,
+                ));
+            } else {
+                n -= left_breaks + node.piece.line_breaks();
+                start += length(&node.left) + node.piece.length();
+                breaks += left_breaks + node.piece.line_breaks();
+                link = &node.right;
+            }
+        }
+
+        None
+    }
+
+    pub fn iter(&self) -> Pieces<'_> {
+        self.iter_from(0)
+    }
+
+    /// iterates pieces in document order starting from the one that contains offset,
+    /// each item is a piece with the document offset where it starts
+    pub fn iter_from(&self, offset: usize) -> Pieces<'_> {
+        let mut stack = Vec::new();
+        let mut link = &self.root;
+        let mut base = 0;
+
+        while let Some(node) = link {
+            let start = base + length(&node.left);
+
+            if offset < start {
+                stack.push((node.as_ref(), start));
+                link = &node.left;
+            } else if offset < start + node.piece.length() {
+                stack.push((node.as_ref(), start));
+                break;
+            } else {
+                base = start + node.piece.length();
+                link = &node.right;
+            }
+        }
+
+        Pieces { stack }
+    }
+
+    /// items before offset go to the left tree and the rest to the right one
+    fn split<F>(&mut self, link: Link, offset: usize, split_piece: &F) -> (Link, Link)
+    where
+        F: Fn(&Piece, usize) -> (Piece, Piece),
+    {
+        let mut node = match link {
+            Some(n) => n,
+            None => return (None, None),
+        };
+
+        let left_len = length(&node.left);
+        let piece_len = node.piece.length();
+
+        if offset <= left_len {
+            let (left, right) = self.split(node.left.take(), offset, split_piece);
+            node.left = right;
+            node.update();
+            (left, Some(node))
+        } else if offset >= left_len + piece_len {
+            let (left, right) = self.split(
+                node.right.take(),
+                offset - left_len - piece_len,
+                split_piece,
+            );
+            node.right = left;
+            node.update();
+            (Some(node), right)
+        } else {
+            let (before, after) = split_piece(&node.piece, offset - left_len);
+            let right = node.right.take();
+            node.piece = before;
+            node.update();
+
+            let after = Node::new(after, self.next_priority());
+            (Some(node), merge(Some(after), right))
+        }
+    }
+
+    /// xorshift, treap only needs priorities to be spread evenly
+    fn next_priority(&mut self) -> u32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        self.seed
+    }
+}
+
+/// joins 2 trees, all items of the left one are placed before items of the right one
+fn merge(left: Link, right: Link) -> Link {
+    match (left, right) {
+        (None, right) => right,
+        (left, None) => left,
+        (Some(mut l), Some(mut r)) => {
+            if l.priority > r.priority {
+                l.right = merge(l.right.take(), Some(r));
+                l.update();
+                Some(l)
+            } else {
+                r.left = merge(Some(l), r.left.take());
+                r.update();
+                Some(r)
+            }
+        }
+    }
+}
+
+pub struct Pieces<'a> {
+    stack: Vec<(&'a Node, usize)>,
+}
+
+impl<'a> Iterator for Pieces<'a> {
+    type Item = (&'a Piece, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, start) = self.stack.pop()?;
+
+        // next pieces are the leftmost path of the right subtree, left children start where
+        // their parent's subtree starts
+        let mut link = &node.right;
+        let base = start + node.piece.length();
+        while let Some(n) = link {
+            self.stack.push((n.as_ref(), base + length(&n.left)));
+            link = &n.left;
+        }
+
+        Some((&node.piece, start))
+    }
+}
@@ -0,0 +1,346 @@
+use crate::window::piece_table::PieceTable;
+use crate::window::segment::SegmentNode;
+use regex::{Captures, Regex, RegexBuilder};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Forward,
+    Backward,
+}
+
+impl Direction {
+    pub fn reverse(self) -> Direction {
+        match self {
+            Direction::Forward => Direction::Backward,
+            Direction::Backward => Direction::Forward,
+        }
+    }
+}
+
+/// searched pattern, patterns without special characters are matched as plain text
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Pattern, String> {
+        if regex::escape(pattern) == pattern {
+            return Ok(Pattern::Literal(pattern.to_string()));
+        }
+
+        compile(pattern, false).map(Pattern::Regex)
+    }
+}
+
+pub fn compile(pattern: &str, ignore_case: bool) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(ignore_case)
+        .build()
+        .map_err(|_| format!("Invalid pattern: {}", pattern))
+}
+
+/// match of a substitution, text replaces len items at the offset
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replacement {
+    pub offset: usize,
+    pub len: usize,
+    pub text: String,
+    pub line: usize,
+}
+
+/// finds a pattern in a stream of items (Knuth–Morris–Pratt), so the document can be fed piece
+/// by piece and a match may start in one piece and end in another
+struct Matcher<'a> {
+    pattern: &'a [u8],
+    /// length of the longest proper prefix of pattern[..=i] that is also it's suffix
+    fallback: Vec<usize>,
+    matched: usize,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(pattern: &'a [u8]) -> Matcher<'a> {
+        let mut fallback = vec![0; pattern.len()];
+        let mut len = 0;
+
+        for i in 1..pattern.len() {
+            while len > 0 && pattern[i] != pattern[len] {
+                len = fallback[len - 1];
+            }
+            if pattern[i] == pattern[len] {
+                len += 1;
+            }
+            fallback[i] = len;
+        }
+
+        Matcher {
+            pattern,
+            fallback,
+            matched: 0,
+        }
+    }
+
+    /// true when the item completes a match
+    fn feed(&mut self, item: u8) -> bool {
+        while self.matched > 0 && item != self.pattern[self.matched] {
+            self.matched = self.fallback[self.matched - 1];
+        }
+        if item == self.pattern[self.matched] {
+            self.matched += 1;
+        }
+        if self.matched == self.pattern.len() {
+            self.matched = self.fallback[self.matched - 1];
+            return true;
+        }
+
+        false
+    }
+}
+
+/// calls f with the offset of every match that starts at from or later, until f returns false
+fn scan<F: FnMut(usize) -> bool>(data: &PieceTable, pattern: &str, from: usize, mut f: F) {
+    if pattern.is_empty() {
+        return;
+    }
+
+    let mut matcher = Matcher::new(pattern.as_bytes());
+    for (start, text) in data.chunks_from(from) {
+        for (i, item) in text.bytes().enumerate() {
+            if matcher.feed(item) && !f(start + i + 1 - pattern.len()) {
+                return;
+            }
+        }
+    }
+}
+
+/// offset of the closest match after (or before) the offset, and whether the search had to wrap
+/// around the end (or start) of the document to find it
+pub fn find(
+    data: &PieceTable,
+    pattern: &Pattern,
+    offset: usize,
+    direction: Direction,
+) -> Option<(usize, bool)> {
+    match pattern {
+        Pattern::Literal(p) => find_literal(data, p, offset, direction),
+        Pattern::Regex(r) => find_regex(data, r, offset, direction),
+    }
+}
+
+/// matches within the range as (offset, length), matches don't overlap
+pub fn find_in(
+    data: &PieceTable,
+    pattern: &Pattern,
+    from: usize,
+    to: usize,
+) -> Vec<(usize, usize)> {
+    match pattern {
+        Pattern::Literal(p) => find_literal_in(data, p, from, to),
+        Pattern::Regex(r) => {
+            let lines = data.get_lines(data.line_at(from), data.line_at(to.saturating_sub(1)));
+            let mut res = Vec::new();
+
+            for node in lines.nodes.iter() {
+                for m in r.find_iter(content(node)) {
+                    let offset = node.offset + m.start();
+                    if offset >= from && offset + m.len() <= to && !m.is_empty() {
+                        res.push((offset, m.len()));
+                    }
+                }
+            }
+
+            res
+        }
+    }
+}
+
+fn find_literal(
+    data: &PieceTable,
+    pattern: &str,
+    offset: usize,
+    direction: Direction,
+) -> Option<(usize, bool)> {
+    let mut found = None;
+
+    match direction {
+        Direction::Forward => {
+            scan(data, pattern, offset + 1, |o| {
+                found = Some((o, false));
+                false
+            });
+            if found.is_none() {
+                scan(data, pattern, 0, |o| {
+                    found = Some((o, true));
+                    false
+                });
+            }
+        }
+        Direction::Backward => {
+            // pieces are only walked forward, so the last match before the offset is kept, and
+            // the last one in the document in case the search wraps
+            let mut last = None;
+            scan(data, pattern, 0, |o| {
+                if o < offset {
+                    found = Some((o, false));
+                    return true;
+                }
+                last = Some((o, true));
+                found.is_none()
+            });
+            found = found.or(last);
+        }
+    }
+
+    found
+}
+
+fn find_literal_in(
+    data: &PieceTable,
+    pattern: &str,
+    from: usize,
+    to: usize,
+) -> Vec<(usize, usize)> {
+    let mut res: Vec<(usize, usize)> = Vec::new();
+
+    scan(data, pattern, from, |o| {
+        if o + pattern.len() > to {
+            return false;
+        }
+        if res.last().is_none_or(|last| last.0 + last.1 <= o) {
+            res.push((o, pattern.len()));
+        }
+        true
+    });
+
+    res
+}
+
+fn line(data: &PieceTable, ln: usize) -> Option<SegmentNode> {
+    data.get_lines(ln, ln).front().cloned()
+}
+
+/// line value without the line break
+fn content(node: &SegmentNode) -> &str {
+    node.value.strip_suffix('\n').unwrap_or(&node.value)
+}
+
+/// regex can't be fed piece by piece, so lines are matched one at a time, starting from the line
+/// with the offset. matches don't span lines.
+fn find_regex(
+    data: &PieceTable,
+    regex: &Regex,
+    offset: usize,
+    direction: Direction,
+) -> Option<(usize, bool)> {
+    let current = data.line_at(offset);
+    let count = data.line_count();
+
+    match direction {
+        Direction::Forward => {
+            for ln in current..=count {
+                let node = line(data, ln)?;
+                let text = content(&node);
+                let mut start = (offset + 1).saturating_sub(node.offset);
+                while start < text.len() && !text.is_char_boundary(start) {
+                    start += 1;
+                }
+                if start > text.len() {
+                    continue;
+                }
+                if let Some(m) = regex.find_at(text, start) {
+                    return Some((node.offset + m.start(), false));
+                }
+            }
+
+            for ln in 1..=current {
+                let node = line(data, ln)?;
+                if let Some(m) = regex.find(content(&node)) {
+                    return Some((node.offset + m.start(), true));
+                }
+            }
+        }
+        Direction::Backward => {
+            for ln in (1..=current).rev() {
+                let node = line(data, ln)?;
+                let last = regex
+                    .find_iter(content(&node))
+                    .map(|m| node.offset + m.start())
+                    .take_while(|o| *o < offset)
+                    .last();
+                if let Some(o) = last {
+                    return Some((o, false));
+                }
+            }
+
+            for ln in (current..=count).rev() {
+                let node = line(data, ln)?;
+                let last = regex
+                    .find_iter(content(&node))
+                    .map(|m| node.offset + m.start())
+                    .last()
+                    .filter(|o| *o >= offset);
+                if let Some(o) = last {
+                    return Some((o, true));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// replacements for matches of the regex in lines from..=to, only the first match of a line
+/// is replaced unless global. see expand for references in the replacement.
+pub fn substitutions(
+    data: &PieceTable,
+    regex: &Regex,
+    replacement: &str,
+    lines: (usize, usize),
+    global: bool,
+) -> Vec<Replacement> {
+    let mut res = Vec::new();
+
+    for node in data.get_lines(lines.0, lines.1).nodes.iter() {
+        for caps in regex.captures_iter(content(node)) {
+            let m = caps.get(0).unwrap();
+            res.push(Replacement {
+                offset: node.offset + m.start(),
+                len: m.len(),
+                text: expand(replacement, &caps),
+                line: node.line_number,
+            });
+
+            if !global {
+                break;
+            }
+        }
+    }
+
+    res
+}
+
+/// replacement text for the match, & and \0 stand for the whole match, \1 - \9 for groups and
+/// \r or \n for a line break. other escaped items are taken as they are, e.g. \& or \/.
+pub fn expand(replacement: &str, caps: &Captures) -> String {
+    let mut res = String::new();
+    let mut chars = replacement.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => res.push_str(&caps[0]),
+            '\\' => match chars.next() {
+                Some(d) if d.is_ascii_digit() => {
+                    let group = d.to_digit(10).unwrap() as usize;
+                    res.push_str(caps.get(group).map_or("", |m| m.as_str()));
+                }
+                Some('r') | Some('n') => res.push('\n'),
+                Some(other) => res.push(other),
+                None => res.push('\\'),
+            },
+            _ => res.push(c),
+        }
+    }
+
+    res
+}
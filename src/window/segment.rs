@@ -25,6 +25,12 @@ pub struct Segment {
     pub nodes: VecDeque<SegmentNode>,
 }
 
+impl Default for Segment {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Segment {
     pub fn new() -> Segment {
         Segment {
@@ -53,63 +59,23 @@ impl Segment {
         }
     }
 
-    pub fn update_at(&mut self, ln: usize, val: &String) {
+    pub fn update_at(&mut self, ln: usize, val: &str) {
         let idx = match self.get_line_idx(ln) {
             Ok(i) => i,
             Err(_) => return,
         };
 
+        let mut delta = 0;
         if let Some(node) = self.nodes.get_mut(idx) {
-            node.value = val.clone();
+            delta = val.len() as isize - node.value.len() as isize;
+            node.value = val.to_string();
             node.updated = true;
         }
-    }
 
-    pub fn insert_at(&mut self, ln: usize, new_node: &String) {
-        let idx = self.get_line_idx(ln);
-        if idx.is_err() {
-            return;
+        // lines below moved in the document by the change in length
+        for node in self.nodes.iter_mut().skip(idx + 1) {
+            node.offset = node.offset.saturating_add_signed(delta);
         }
-        let idx = idx.unwrap();
-
-        let mut temp = VecDeque::new();
-
-        self.nodes.pop_back();
-        let mut i = 0;
-        if idx > i {
-            while i < idx {
-                temp.push_back(self.nodes.pop_front().unwrap());
-                i += 1;
-            }
-
-            let last = &temp.back().unwrap();
-            temp.push_back(SegmentNode {
-                value: new_node.clone(),
-                line_number: ln,
-                offset: last.offset + last.value.len(),
-                updated: true,
-            });
-        } else {
-            let first = self.nodes.front().unwrap();
-            temp.push_back(SegmentNode {
-                value: new_node.clone(),
-                line_number: ln,
-                offset: first.offset,
-                updated: true,
-            });
-            // temp.push_back()
-        }
-
-        while let Some(n) = self.nodes.pop_front() {
-            temp.push_back(SegmentNode {
-                value: n.value,
-                line_number: n.line_number + 1,
-                offset: n.offset,
-                updated: true,
-            });
-        }
-
-        self.nodes = temp;
     }
 
     pub fn add_b(&mut self, n: SegmentNode) {
@@ -124,10 +90,6 @@ impl Segment {
         self.add_b(SegmentNode::new(v, ln, ofst))
     }
 
-    pub fn new_f(&mut self, v: String, ln: usize, ofst: usize) {
-        self.add_f(SegmentNode::new(v, ln, ofst))
-    }
-
     pub fn back(&self) -> Option<&SegmentNode> {
         self.nodes.back()
     }
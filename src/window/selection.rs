@@ -0,0 +1,114 @@
+use crate::window::columns;
+use crate::window::piece_table::PieceTable;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelectionKind {
+    Char,
+    Line,
+    Block,
+}
+
+/// selection between the anchor, where visual mode was started, and the cursor.
+/// positions are (line, column) as the cursor keeps them, columns count graphemes. both ends
+/// are included.
+#[derive(Clone, Debug)]
+pub struct Selection {
+    pub kind: SelectionKind,
+    pub anchor: (usize, usize),
+}
+
+impl Selection {
+    pub fn new(kind: SelectionKind, anchor: (usize, usize)) -> Selection {
+        Selection { kind, anchor }
+    }
+
+    /// first and last selected lines
+    pub fn lines(&self, cursor: (usize, usize)) -> (usize, usize) {
+        (self.anchor.0.min(cursor.0), self.anchor.0.max(cursor.0))
+    }
+
+    /// selected items of the line as a range of indexes in the line value, None when the line
+    /// is not selected
+    pub fn columns(
+        &self,
+        cursor: (usize, usize),
+        ln: usize,
+        value: &str,
+    ) -> Option<(usize, usize)> {
+        let (first, last) = self.lines(cursor);
+        if ln < first || ln > last {
+            return None;
+        }
+
+        let len = value.len();
+
+        match self.kind {
+            SelectionKind::Line => Some((0, len)),
+            SelectionKind::Char => {
+                let (start, end) = if self.anchor <= cursor {
+                    (self.anchor, cursor)
+                } else {
+                    (cursor, self.anchor)
+                };
+
+                let from = if ln == start.0 {
+                    columns::byte_of(value, start.1)
+                } else {
+                    0
+                };
+                // the column after the last grapheme takes the line break
+                let to = if ln == end.0 && end.1 <= columns::count(value) {
+                    columns::byte_of(value, end.1 + 1)
+                } else {
+                    len
+                };
+
+                Some((from, to))
+            }
+            SelectionKind::Block => {
+                let from = columns::byte_of(value, self.anchor.1.min(cursor.1));
+                let to = columns::byte_of(value, self.anchor.1.max(cursor.1) + 1);
+
+                if from < to {
+                    Some((from, to))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// selected document ranges as (offset, length), charwise and linewise selections are one
+    /// range, blockwise has a range per line. ranges are sorted by offset.
+    pub fn ranges(&self, cursor: (usize, usize), data: &PieceTable) -> Vec<(usize, usize)> {
+        let (first, last) = self.lines(cursor);
+        let mut res: Vec<(usize, usize)> = Vec::new();
+
+        for node in data.get_lines(first, last).nodes.iter() {
+            let (from, to) = match self.columns(cursor, node.line_number, &node.value) {
+                Some(c) => c,
+                None => continue,
+            };
+
+            let offset = node.offset + from;
+            match res.last_mut() {
+                Some(prev) if self.kind != SelectionKind::Block && prev.0 + prev.1 == offset => {
+                    prev.1 += to - from;
+                }
+                _ => res.push((offset, to - from)),
+            }
+        }
+
+        res.retain(|r| r.1 > 0);
+        res
+    }
+
+    /// position where the selection starts, cursor is placed there after an operator
+    pub fn start(&self, cursor: (usize, usize)) -> (usize, usize) {
+        match self.kind {
+            SelectionKind::Char => self.anchor.min(cursor),
+            SelectionKind::Line => (self.lines(cursor).0, 1),
+            SelectionKind::Block => (self.lines(cursor).0, self.anchor.1.min(cursor.1)),
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::window::columns;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+// soft wrapping shows a line that is wider than the screen on many rows, the text itself
+// doesn't change. rows are found by byte indexes in the line where they start. tabs keep
+// their width from the start of the line, so a row may start in the middle of a tab stop.
+
+/// how lines are split into rows of the screen
+#[derive(Clone, Debug)]
+pub struct Layout {
+    /// lines longer than the width continue on the next row, otherwise they are cut
+    pub wrap: bool,
+    /// screen columns a row of text has
+    pub width: usize,
+    pub tabstop: usize,
+    /// rows end after white space instead of in the middle of a word
+    pub linebreak: bool,
+    /// shown at the start of rows that continue a line, it takes their space
+    pub showbreak: String,
+}
+
+fn content(line: &str) -> &str {
+    line.strip_suffix('\n').unwrap_or(line)
+}
+
+fn is_blank(grapheme: &str) -> bool {
+    grapheme == " " || grapheme == "\t"
+}
+
+impl Layout {
+    /// columns taken by showbreak at the start of continued rows
+    pub fn showbreak_width(&self) -> usize {
+        self.showbreak.width()
+    }
+
+    /// byte indexes in the line where it's rows start, the first one is always 0. a grapheme
+    /// that doesn't fit goes to the next row, with linebreak the word before it goes as well.
+    pub fn rows(&self, line: &str) -> Vec<usize> {
+        let mut starts = vec![0];
+        if !self.wrap {
+            return starts;
+        }
+
+        let next_width = self.width.saturating_sub(self.showbreak_width()).max(1);
+        let mut limit = self.width.max(1);
+        // width used in the row, and where the row may end after white space
+        let mut used = 0;
+        let mut blank: Option<(usize, usize)> = None;
+        let mut x = 0;
+
+        for (idx, g) in content(line).grapheme_indices(true) {
+            let w = columns::width(g, x, self.tabstop);
+            while used > 0 && used + w > limit {
+                match blank.take().filter(|_| self.linebreak) {
+                    Some((start, before)) => {
+                        starts.push(start);
+                        used -= before;
+                    }
+                    None => {
+                        starts.push(idx);
+                        used = 0;
+                    }
+                }
+                limit = next_width;
+            }
+
+            used += w;
+            x += w;
+            if is_blank(g) {
+                blank = Some((idx + g.len(), used));
+            }
+        }
+
+        starts
+    }
+
+    /// row of the byte index, counted from 0
+    pub fn row_of(&self, rows: &[usize], idx: usize) -> usize {
+        rows.partition_point(|start| *start <= idx)
+            .saturating_sub(1)
+    }
+
+    /// screen column of the byte index in it's row counted from 1, showbreak is counted in
+    pub fn x_of(&self, line: &str, rows: &[usize], idx: usize) -> usize {
+        let row = self.row_of(rows, idx);
+        let start = rows[row];
+
+        let mut x = 0;
+        let mut row_x = 0;
+        for (i, g) in content(line).grapheme_indices(true) {
+            if i == start {
+                row_x = x;
+            }
+            if i >= idx {
+                break;
+            }
+            x += columns::width(g, x, self.tabstop);
+        }
+
+        let offset = if row > 0 { self.showbreak_width() } else { 0 };
+        x.max(row_x) - row_x + offset + 1
+    }
+
+    /// byte index of the grapheme at the screen column of the row, or of the last one in the
+    /// row when the row is shorter
+    pub fn idx_at(&self, line: &str, rows: &[usize], row: usize, x: usize) -> usize {
+        let start = rows[row.min(rows.len() - 1)];
+        let end = rows.get(row + 1).copied();
+        let offset = if row > 0 { self.showbreak_width() } else { 0 };
+
+        let mut vx = 0;
+        let mut row_x = offset + 1;
+        let mut found = start;
+        for (i, g) in content(line).grapheme_indices(true) {
+            let w = columns::width(g, vx, self.tabstop);
+            vx += w;
+            if i < start {
+                continue;
+            }
+            if end.is_some_and(|e| i >= e) {
+                break;
+            }
+
+            found = i;
+            row_x += w;
+            if row_x > x {
+                break;
+            }
+        }
+
+        found
+    }
+}
@@ -0,0 +1,29 @@
+use text_editor::clipboard;
+
+#[test]
+fn osc52_escape_carries_base64_text() {
+    let mut out: Vec<u8> = Vec::new();
+    clipboard::write_osc52(&mut out, clipboard::selection('+'), "hi there").unwrap();
+    assert_eq!(out, b"\x1b]52;c;aGkgdGhlcmU=\x07");
+
+    let mut out: Vec<u8> = Vec::new();
+    clipboard::write_osc52(&mut out, clipboard::selection('*'), "").unwrap();
+    assert_eq!(out, b"\x1b]52;p;\x07");
+}
+
+#[test]
+fn clipboard_commands() {
+    assert_eq!(clipboard::copy("cat", "text"), Ok(()));
+    assert_eq!(
+        clipboard::paste("echo pasted"),
+        Ok(String::from("pasted\n"))
+    );
+
+    // output of the copy command and input of the paste one are not waited on
+    assert_eq!(clipboard::copy("cat", &"x".repeat(1 << 20)), Ok(()));
+    assert_eq!(clipboard::paste("cat"), Ok(String::new()));
+
+    assert!(clipboard::copy("no-such-clipboard-command", "text").is_err());
+    assert!(clipboard::paste("false").is_err());
+    assert!(clipboard::paste("").is_err());
+}
@@ -0,0 +1,76 @@
+use text_editor::window::columns;
+use text_editor::window::selection::{Selection, SelectionKind};
+
+#[test]
+fn graphemes_are_one_column() {
+    // e with a combining acute accent, a family emoji made of joined chars
+    let line = "e\u{301}x👨‍👩‍👧y\n";
+    assert_eq!(columns::count(line), 4);
+
+    assert_eq!(columns::byte_of(line, 1), 0);
+    assert_eq!(columns::byte_of(line, 2), 3);
+    assert_eq!(columns::byte_of(line, 3), 4);
+    assert_eq!(columns::byte_of(line, 4), line.len() - 2);
+    assert_eq!(columns::byte_of(line, 5), line.len() - 1);
+    assert_eq!(columns::byte_of(line, 9), line.len() - 1);
+
+    assert_eq!(columns::col_of(line, 1), 1);
+    assert_eq!(columns::col_of(line, 6), 3);
+    assert_eq!(columns::col_of(line, line.len() - 1), 5);
+    assert_eq!(columns::col_of("", 0), 1);
+}
+
+#[test]
+fn wide_items_and_tabs_take_more_of_the_screen() {
+    let line = "日本x\tz";
+    assert_eq!(columns::screen_col(line, 1, 8), 1);
+    assert_eq!(columns::screen_col(line, 2, 8), 3);
+    assert_eq!(columns::screen_col(line, 3, 8), 5);
+    assert_eq!(columns::screen_col(line, 4, 8), 6);
+    assert_eq!(columns::screen_col(line, 5, 8), 9);
+
+    assert_eq!(columns::screen_col("a\u{301}b", 2, 8), 2);
+    assert_eq!(columns::screen_col("🙂b", 2, 8), 3);
+    assert_eq!(columns::width("\t", 6, 4), 2);
+}
+
+#[test]
+fn screen_columns_turn_back_into_columns() {
+    let line = "日本x\tz\n";
+    assert_eq!(columns::col_at(line, 1, 8), 1);
+    assert_eq!(columns::col_at(line, 2, 8), 1);
+    assert_eq!(columns::col_at(line, 4, 8), 2);
+    assert_eq!(columns::col_at(line, 7, 8), 4);
+    assert_eq!(columns::col_at(line, 9, 8), 5);
+    assert_eq!(columns::col_at(line, 10, 8), 6);
+    assert_eq!(columns::col_at("ab", 5, 8), 5);
+}
+
+#[test]
+fn selection_columns_are_graphemes() {
+    let line = "αβγδ\n";
+
+    let selection = Selection::new(SelectionKind::Char, (1, 2));
+    assert_eq!(selection.columns((1, 3), 1, line), Some((2, 6)));
+    // the column after the last item takes the line break
+    assert_eq!(selection.columns((1, 5), 1, line), Some((2, 9)));
+
+    let selection = Selection::new(SelectionKind::Block, (1, 4));
+    assert_eq!(selection.columns((2, 2), 1, line), Some((2, 8)));
+    assert_eq!(selection.columns((2, 2), 2, "日\n"), None);
+}
+
+#[test]
+fn tabs_are_expanded_to_the_next_tab_stop() {
+    let mut x = 0;
+    assert_eq!(columns::expand_tabs("a\tb\n\tc", &mut x, 4), "a   b\n    c");
+    assert_eq!(x, 5);
+
+    // the text goes on from the column where the last part ended
+    let mut x = 2;
+    assert_eq!(columns::expand_tabs("日\t", &mut x, 8), "日    ");
+    assert_eq!(x, 8);
+
+    assert_eq!(columns::indent(10, 4, false), "\t\t  ");
+    assert_eq!(columns::indent(10, 4, true), " ".repeat(10));
+}
@@ -0,0 +1,116 @@
+use text_editor::command::{parse, Address, Command, Range};
+use text_editor::options::Options;
+
+#[test]
+fn parses_abbreviations_and_force() {
+    assert_eq!(
+        parse("w"),
+        Ok(Command::Write {
+            path: None,
+            force: false
+        })
+    );
+    assert_eq!(parse("q!"), Ok(Command::Quit { force: true }));
+    assert_eq!(
+        parse(":x"),
+        Ok(Command::Exit {
+            path: None,
+            force: false
+        })
+    );
+    assert_eq!(
+        parse("x! out.txt"),
+        Ok(Command::Exit {
+            path: Some(String::from("out.txt")),
+            force: true
+        })
+    );
+    assert_eq!(
+        parse("e! notes.txt"),
+        Ok(Command::Edit {
+            path: Some(String::from("notes.txt")),
+            force: true
+        })
+    );
+    assert_eq!(parse("42"), Ok(Command::Goto(42)));
+}
+
+#[test]
+fn reports_bad_commands() {
+    assert_eq!(parse(""), Err(String::new()));
+    assert!(parse("foo")
+        .unwrap_err()
+        .starts_with("Not an editor command"));
+    assert!(parse("q now")
+        .unwrap_err()
+        .starts_with("Trailing characters"));
+    assert!(parse("sav").is_err());
+}
+
+#[test]
+fn set_changes_and_shows_options() {
+    let mut options = Options::new();
+
+    assert_eq!(options.set("hi=10"), Ok(None));
+    assert_eq!(options.history, 10);
+    assert_eq!(
+        options.set("history?"),
+        Ok(Some(String::from("history=10")))
+    );
+    assert_eq!(options.show_changed(), "history=10");
+    assert_eq!(options.set("hi&"), Ok(None));
+    assert_eq!(options.history, 50);
+    assert!(options.set("hi=x").is_err());
+    assert!(options.set("nosuch").is_err());
+
+    assert_eq!(options.set("noosc52"), Ok(None));
+    assert!(!options.osc52);
+    let args = match parse("set ccp=xclip\\ -selection\\ clipboard osc") {
+        Ok(Command::Set(args)) => args,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(args, ["ccp=xclip -selection clipboard", "osc"]);
+    for arg in args.iter() {
+        options.set(arg).unwrap();
+    }
+    assert_eq!(options.clipcopy, "xclip -selection clipboard");
+    assert!(options.osc52);
+
+    assert_eq!(options.set("ts=4"), Ok(None));
+    assert_eq!(options.set("sw=0"), Ok(None));
+    assert_eq!(options.shift_width(), 4);
+    assert!(options.set("ts=0").is_err());
+    assert_eq!(options.set("et"), Ok(None));
+    assert!(options
+        .show_changed()
+        .ends_with("tabstop=4  shiftwidth=0  expandtab"));
+}
+
+#[test]
+fn parses_substitute_with_ranges() {
+    let cmd = match parse("'<,'>s#a\\#b#\\1#gi") {
+        Ok(Command::Substitute(s)) => s,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(
+        cmd.range,
+        Some(Range {
+            from: Address::Mark('<'),
+            to: Address::Mark('>')
+        })
+    );
+    assert_eq!(cmd.pattern, r"a\#b");
+    assert_eq!(cmd.replacement, "\\1");
+    assert!(cmd.global && cmd.ignore_case && !cmd.confirm);
+
+    let cmd = match parse("%s/x/y") {
+        Ok(Command::Substitute(s)) => s,
+        other => panic!("{:?}", other),
+    };
+    assert_eq!(cmd.range.map(|r| r.to), Some(Address::Last));
+    assert_eq!((cmd.pattern.as_str(), cmd.replacement.as_str()), ("x", "y"));
+
+    assert_eq!(parse("$"), Ok(Command::Goto(usize::MAX)));
+    assert!(parse("2,3w").is_err());
+    assert!(parse("s/a/b/z").is_err());
+}
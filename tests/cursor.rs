@@ -0,0 +1,22 @@
+use text_editor::window::cursor::Cursor;
+
+#[test]
+fn cursor_fits_into_a_smaller_screen() {
+    let mut cursor = Cursor {
+        x: 3,
+        vertical_x: 3,
+        relative_y: 20,
+        absolute_y: 40,
+        screen_x: None,
+    };
+
+    // the line stays, the view scrolls so it's on the last row
+    cursor.fit(9);
+    assert_eq!((cursor.relative_y, cursor.absolute_y), (9, 40));
+
+    cursor.fit(30);
+    assert_eq!((cursor.relative_y, cursor.absolute_y), (9, 40));
+
+    cursor.fit(0);
+    assert_eq!(cursor.relative_y, 1);
+}
@@ -0,0 +1,473 @@
+use std::path::PathBuf;
+use termion::event::{Event, Key};
+use text_editor::backend::screen::Screen;
+use text_editor::backend::{CursorShape, Style};
+use text_editor::codes;
+use text_editor::editor::Editor;
+use text_editor::input::Input;
+use text_editor::keys;
+use text_editor::stdio::Stdio;
+use text_editor::window::buffer::Buffer;
+
+/// editor with the file opened on a 40x10 screen in memory
+fn open(name: &str, text: &str) -> (Editor, Buffer, Screen, PathBuf) {
+    let path = std::env::temp_dir().join(format!("text_editor_{}_{}", std::process::id(), name));
+    std::fs::write(&path, text).unwrap();
+
+    let screen = Screen::new(40, 10);
+    let stdio = Stdio::new(Box::new(screen.clone()));
+    let buffer = Buffer::new(Some(path.display().to_string()), stdio);
+    (Editor::new(), buffer, screen, path)
+}
+
+/// keys are written the way they are kept in registers, e.g. `\x1b` is Esc
+fn feed(editor: &mut Editor, buffer: &mut Buffer, keys: &str) {
+    for key in keys::decode(keys) {
+        editor.handle_input(buffer, Input::Event(Event::Key(key)));
+    }
+}
+
+#[test]
+fn types_text_and_saves_the_file() {
+    let (mut editor, mut buffer, screen, path) = open("save", "world\n");
+    assert_eq!(screen.line(1), "world");
+    assert_eq!(screen.shape(), CursorShape::Block);
+
+    feed(&mut editor, &mut buffer, "ihello ");
+    assert_eq!(screen.shape(), CursorShape::Bar);
+    assert_eq!(screen.line(1), "hello world");
+    assert_eq!(screen.cursor(), (7, 1));
+
+    feed(&mut editor, &mut buffer, "\x1b:wq\n");
+    assert!(editor.has_quit());
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "hello world\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn deletes_lines_and_undoes() {
+    let (mut editor, mut buffer, screen, path) = open("undo", "one\ntwo\nthree\n");
+
+    feed(&mut editor, &mut buffer, "jdd");
+    assert_eq!(screen.lines()[..3], ["one", "three", ""]);
+    assert_eq!(screen.cursor(), (1, 2));
+
+    feed(&mut editor, &mut buffer, "u");
+    assert_eq!(screen.lines()[..3], ["one", "two", "three"]);
+    assert!(screen.line(10).starts_with("1 changes undone"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn selection_and_matches_are_styled() {
+    let (mut editor, mut buffer, screen, path) = open("style", "foo bar foo\n");
+
+    feed(&mut editor, &mut buffer, "vl");
+    assert_eq!(screen.cell(1, 1).style, Style::Selection);
+    assert_eq!(screen.cell(2, 1).style, Style::Selection);
+    assert_eq!(screen.cell(3, 1).style, Style::Normal);
+
+    feed(&mut editor, &mut buffer, "\x1b/foo\n");
+    assert_eq!(screen.cell(1, 1).style, Style::Match);
+    assert_eq!(screen.cell(9, 1).style, Style::Match);
+    assert_eq!(screen.cell(4, 1).style, Style::Normal);
+    assert_eq!(screen.cursor(), (9, 1));
+
+    feed(&mut editor, &mut buffer, ":nosuch\n");
+    assert_eq!(screen.cell(1, 10).style, Style::Error);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn wide_graphemes_and_tabs_move_the_cursor_on_screen() {
+    let (mut editor, mut buffer, screen, path) = open("wide", "日本語\n\tx\n");
+    assert_eq!(screen.line(2), "        x");
+
+    feed(&mut editor, &mut buffer, "ll");
+    assert_eq!(screen.cursor(), (5, 1));
+    feed(&mut editor, &mut buffer, "x");
+    assert_eq!(screen.line(1), "日本");
+
+    feed(&mut editor, &mut buffer, "j0l");
+    assert_eq!(screen.cursor(), (9, 2));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn redraws_for_a_fake_resize() {
+    let text: String = (1..=30).map(|n| format!("{}\n", n)).collect();
+    let (mut editor, mut buffer, screen, path) = open("resize", &text);
+
+    feed(&mut editor, &mut buffer, "7j");
+    assert_eq!(screen.cursor(), (1, 8));
+
+    screen.resize(30, 5);
+    editor.handle_input(&mut buffer, Input::Resize((30, 5)));
+    assert_eq!(screen.lines()[..3], ["6", "7", "8"]);
+    assert_eq!(screen.cursor(), (1, 3));
+    assert!(screen.line(4).ends_with("8:1  26%"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn clipboard_register_is_sent_to_the_terminal() {
+    let (mut editor, mut buffer, screen, path) = open("clipboard", "copied\n");
+
+    feed(&mut editor, &mut buffer, "\"+yy");
+    assert_eq!(screen.clipboard(), Some(('c', "copied\n".to_string())));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn typing_draws_only_what_changed() {
+    let (mut editor, mut buffer, screen, path) = open("damage", "one\ntwo\nthree\n");
+
+    feed(&mut editor, &mut buffer, "j$is");
+    let printed = screen.printed();
+    feed(&mut editor, &mut buffer, "s");
+    assert_eq!(screen.line(2), "twsso");
+    // the typed item, the one moved right after it and the column in the status line
+    assert_eq!(screen.printed() - printed, 3);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn gutter_shows_absolute_relative_and_hybrid_numbers() {
+    let text: String = (1..=12).map(|n| format!("line {}\n", n)).collect();
+    let (mut editor, mut buffer, screen, path) = open("gutter", &text);
+
+    feed(&mut editor, &mut buffer, ":set nu\njl");
+    assert_eq!(screen.lines()[..2], ["  1 line 1", "  2 line 2"]);
+    assert_eq!(screen.cursor(), (6, 2));
+    assert_eq!(screen.cell(3, 2).style, Style::CurrentLineNr);
+    assert_eq!(screen.cell(3, 1).style, Style::LineNr);
+
+    feed(&mut editor, &mut buffer, ":set nonu rnu\n");
+    assert_eq!(
+        screen.lines()[..3],
+        ["  1 line 1", "  0 line 2", "  1 line 3"]
+    );
+
+    feed(&mut editor, &mut buffer, ":set nu\nj");
+    assert_eq!(
+        screen.lines()[..3],
+        ["  2 line 1", "  1 line 2", "3   line 3"]
+    );
+    assert_eq!(screen.cell(1, 3).style, Style::CurrentLineNr);
+
+    // typing keeps the number of the line
+    feed(&mut editor, &mut buffer, "ix");
+    assert_eq!(screen.line(3), "3   lxine 3");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn status_line_shows_mode_file_and_position() {
+    let (mut editor, mut buffer, screen, path) = open("status.rs", "one\ntwo\n");
+
+    feed(&mut editor, &mut buffer, "j");
+    assert!(screen.line(9).starts_with(" NORMAL"));
+    assert!(screen.line(9).ends_with("rust  utf-8  unix  2:1  100%"));
+    assert_eq!(screen.cell(1, 9).style, Style::StatusLine);
+
+    feed(&mut editor, &mut buffer, "ix");
+    assert!(screen.line(9).starts_with(" INSERT"));
+
+    feed(&mut editor, &mut buffer, "\x1b:set stl=%m%=%l\\ of\\ %L\n");
+    assert!(screen.line(9).starts_with("[+]"));
+    assert!(screen.line(9).ends_with("2 of 2"));
+
+    feed(&mut editor, &mut buffer, ":set stl=\n");
+    assert_eq!(screen.line(9).trim(), "");
+    feed(&mut editor, &mut buffer, ":set stl&\n");
+    assert!(screen.line(9).starts_with(" NORMAL"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn wrapped_lines_take_many_rows() {
+    let text = format!(
+        "short\n{}\nc\nd\ne\n{}\nend\n",
+        "x".repeat(100),
+        "z".repeat(100)
+    );
+    let (mut editor, mut buffer, screen, path) = open("wrap", &text);
+    assert_eq!(screen.line(4), "x".repeat(20));
+    // the last line is seen only partly
+    assert_eq!(screen.line(8), "z".repeat(40));
+
+    // j moves by lines, gj and gk by rows of the screen
+    feed(&mut editor, &mut buffer, "jgj");
+    assert_eq!(screen.cursor(), (1, 3));
+    feed(&mut editor, &mut buffer, "jgk");
+    assert_eq!(screen.cursor(), (1, 4));
+    assert!(screen.line(9).ends_with("2:81  28%"));
+
+    // the view scrolls until the row of the cursor is seen
+    feed(&mut editor, &mut buffer, "4j");
+    assert_eq!(screen.lines()[..3], ["c", "d", "e"]);
+    assert_eq!(screen.line(7), "end");
+    assert_eq!(screen.cursor(), (1, 6));
+
+    feed(&mut editor, &mut buffer, ":set sbr=>>\n");
+    assert_eq!(screen.line(5), format!(">>{}", "z".repeat(38)));
+    assert_eq!(screen.cursor(), (5, 6));
+
+    feed(&mut editor, &mut buffer, ":set nowrap\n");
+    assert_eq!(screen.line(4), "z".repeat(40));
+    assert_eq!(screen.line(5), "end");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn exit_writes_to_the_file_given() {
+    let (mut editor, mut buffer, _screen, path) = open("exit", "text\n");
+    let other = path.with_extension("other");
+    std::fs::write(&other, "old\n").unwrap();
+    let cmd = |force: &str| format!(":x{} {}\n", force, other.display());
+
+    feed(&mut editor, &mut buffer, "x");
+    feed(&mut editor, &mut buffer, &cmd(""));
+    assert!(!editor.has_quit());
+    assert_eq!(std::fs::read_to_string(&other).unwrap(), "old\n");
+
+    feed(&mut editor, &mut buffer, &cmd("!"));
+    assert!(editor.has_quit());
+    assert_eq!(std::fs::read_to_string(&other).unwrap(), "ext\n");
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(other).unwrap();
+}
+
+#[test]
+fn searches_from_a_multibyte_char() {
+    let (mut editor, mut buffer, screen, path) = open("multibyte", "é x\n");
+
+    feed(&mut editor, &mut buffer, "/x\n");
+    assert_eq!(screen.cursor(), (3, 1));
+    std::fs::remove_file(path).unwrap();
+}
+
+/// sends the text the way a terminal does with bracketed paste on
+fn paste(editor: &mut Editor, buffer: &mut Buffer, text: &str) {
+    let event = |seq: &[u8]| Input::Event(Event::Unsupported(seq.to_vec()));
+    editor.handle_input(buffer, event(codes::PASTE_START));
+    for ch in text.chars() {
+        editor.handle_input(buffer, Input::Event(Event::Key(Key::Char(ch))));
+    }
+    editor.handle_input(buffer, event(codes::PASTE_END));
+}
+
+#[test]
+fn paste_is_one_edit_and_one_undo_step() {
+    let (mut editor, mut buffer, screen, path) = open("paste", "end\n");
+
+    feed(&mut editor, &mut buffer, "ix");
+    paste(&mut editor, &mut buffer, "one\ntwo ");
+    feed(&mut editor, &mut buffer, "\x1b");
+    assert_eq!(buffer.data.get_string(), "xone\ntwo end\n");
+
+    feed(&mut editor, &mut buffer, "u");
+    assert_eq!(buffer.data.get_string(), "xend\n");
+    assert!(screen.line(10).starts_with("1 changes undone"));
+
+    // in normal mode the text is put before the cursor
+    feed(&mut editor, &mut buffer, "0");
+    paste(&mut editor, &mut buffer, "dd");
+    assert_eq!(buffer.data.get_string(), "ddxend\n");
+    feed(&mut editor, &mut buffer, "u");
+    assert_eq!(buffer.data.get_string(), "xend\n");
+    assert!(screen.line(10).starts_with("1 changes undone"));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn dot_repeats_the_last_change() {
+    let (mut editor, mut buffer, _screen, path) = open("dot", "one two three four\n");
+
+    feed(&mut editor, &mut buffer, "dw.");
+    assert_eq!(buffer.data.get_string(), "three four\n");
+
+    feed(&mut editor, &mut buffer, "x3.");
+    assert_eq!(buffer.data.get_string(), "e four\n");
+
+    feed(&mut editor, &mut buffer, "ihi \x1b0.");
+    assert_eq!(buffer.data.get_string(), "hi hi e four\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn dot_repeats_pasted_text_of_an_insert() {
+    let (mut editor, mut buffer, _screen, path) = open("dot_paste", "end\n");
+
+    feed(&mut editor, &mut buffer, "ia");
+    paste(&mut editor, &mut buffer, "b\n");
+    feed(&mut editor, &mut buffer, "c\x1bgg0.");
+    assert_eq!(buffer.data.get_string(), "ab\ncab\ncend\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn records_a_paste_as_one_paste() {
+    let (mut editor, mut buffer, _screen, path) = open("record_paste", "end\n");
+
+    feed(&mut editor, &mut buffer, "qai");
+    paste(&mut editor, &mut buffer, "xy\n");
+    feed(&mut editor, &mut buffer, "\x1b0");
+    paste(&mut editor, &mut buffer, "dd");
+    feed(&mut editor, &mut buffer, "q");
+    assert_eq!(buffer.data.get_string(), "xy\nddend\n");
+
+    feed(&mut editor, &mut buffer, "0@a");
+    assert_eq!(buffer.data.get_string(), "xy\nxy\nddddend\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn playing_stops_when_a_motion_fails() {
+    let (mut editor, mut buffer, _screen, path) = open("play_fail", "a1\na2\na3\na4\n");
+
+    feed(&mut editor, &mut buffer, "qajxq@a");
+    assert_eq!(buffer.data.get_string(), "a1\n2\n3\na4\n");
+
+    feed(&mut editor, &mut buffer, "5@a");
+    assert_eq!(buffer.data.get_string(), "a1\n2\n3\n4\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn plays_the_last_register_again_with_a_count() {
+    let (mut editor, mut buffer, _screen, path) = open("play_again", "abcdefg\n");
+
+    feed(&mut editor, &mut buffer, "qaxq@a2@@");
+    assert_eq!(buffer.data.get_string(), "efg\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn plays_a_register_edited_in_the_buffer() {
+    let (mut editor, mut buffer, _screen, path) = open("play_edited", "abcdef\nend\n");
+
+    feed(&mut editor, &mut buffer, "qaxqj0D\"apix\x1b");
+    assert_eq!(buffer.data.get_string(), "bcdef\nxx\n");
+
+    feed(&mut editor, &mut buffer, "0\"ay$ddgg@a");
+    assert_eq!(buffer.data.get_string(), "def\n");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn moves_up_and_down_at_the_same_screen_column() {
+    let (mut editor, mut buffer, screen, path) = open("vertical", "\tx\nabcdefghijk\n日本語\n");
+
+    feed(&mut editor, &mut buffer, "$");
+    assert_eq!(screen.cursor(), (9, 1));
+    feed(&mut editor, &mut buffer, "j");
+    assert_eq!(screen.cursor(), (9, 2));
+    feed(&mut editor, &mut buffer, "j");
+    assert_eq!(screen.cursor(), (5, 3));
+    feed(&mut editor, &mut buffer, "k");
+    assert_eq!(screen.cursor(), (9, 2));
+    feed(&mut editor, &mut buffer, "kx");
+    assert_eq!(buffer.data.get_string(), "\t\nabcdefghijk\n日本語\n");
+
+    feed(&mut editor, &mut buffer, "jhk");
+    assert_eq!(screen.cursor(), (1, 1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn quits_when_stdin_ends() {
+    let (mut editor, mut buffer, _screen, path) = open("closed", "text\n");
+
+    feed(&mut editor, &mut buffer, "x");
+    editor.handle_input(&mut buffer, Input::Closed);
+    assert!(editor.has_quit());
+    std::fs::remove_file(path).unwrap();
+}
+
+/// text of count lines, each one is it's own number
+fn numbered(count: usize) -> String {
+    (1..=count).map(|ln| format!("{}\n", ln)).collect()
+}
+
+#[test]
+fn goes_to_lines_after_65535() {
+    let (mut editor, mut buffer, screen, path) = open("goto_far", &numbered(70_000));
+
+    feed(&mut editor, &mut buffer, ":66000\n");
+    assert_eq!(buffer.position(), (66_000, 1));
+    assert_eq!(screen.line(screen.cursor().1), "66000");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn moves_down_past_line_65535() {
+    let (mut editor, mut buffer, screen, path) = open("down_far", &numbered(70_000));
+
+    feed(&mut editor, &mut buffer, ":65535\njj");
+    assert_eq!(buffer.position(), (65_537, 1));
+    assert_eq!(screen.line(screen.cursor().1), "65537");
+
+    feed(&mut editor, &mut buffer, "k");
+    assert_eq!(buffer.position(), (65_536, 1));
+    assert_eq!(screen.line(screen.cursor().1), "65536");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn goes_to_the_last_line_or_a_count_past_65535() {
+    let (mut editor, mut buffer, screen, path) = open("g_far", &numbered(70_000));
+
+    feed(&mut editor, &mut buffer, "G");
+    assert_eq!(buffer.position(), (70_000, 1));
+    assert_eq!(screen.line(screen.cursor().1), "70000");
+
+    feed(&mut editor, &mut buffer, "66000G");
+    assert_eq!(buffer.position(), (66_000, 1));
+    assert_eq!(screen.line(screen.cursor().1), "66000");
+
+    feed(&mut editor, &mut buffer, "gg65537G");
+    assert_eq!(buffer.position(), (65_537, 1));
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn edits_columns_past_65535() {
+    let text = format!("{}b\n", "a".repeat(70_000));
+    let (mut editor, mut buffer, screen, path) = open("long_line", &text);
+
+    feed(&mut editor, &mut buffer, "$");
+    assert_eq!(buffer.position(), (1, 70_001));
+    assert!(screen.line(9).contains("1:70001"));
+
+    feed(&mut editor, &mut buffer, "ix\x1b");
+    assert!(buffer.data.get_string().ends_with("aaxb\n"));
+    assert_eq!(buffer.data.len(), 70_003);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn undo_back_to_the_saved_text_is_not_a_change() {
+    let (mut editor, mut buffer, screen, path) = open("undo_saved", "abc\n");
+
+    feed(&mut editor, &mut buffer, ":set stl=%m\nx");
+    assert_eq!(screen.line(9).trim(), "[+]");
+    feed(&mut editor, &mut buffer, "u");
+    assert!(!buffer.modified);
+    assert_eq!(screen.line(9).trim(), "");
+
+    feed(&mut editor, &mut buffer, "x:w\nu");
+    assert!(buffer.modified);
+    feed(&mut editor, &mut buffer, "\x12");
+    assert!(!buffer.modified);
+
+    // the saved step can't come back after a new change
+    feed(&mut editor, &mut buffer, "uxu");
+    assert!(buffer.modified);
+    feed(&mut editor, &mut buffer, "x:q\n");
+    assert!(!editor.has_quit());
+
+    feed(&mut editor, &mut buffer, ":w\nxu:q\n");
+    assert!(editor.has_quit());
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,59 @@
+use text_editor::backend::frame::{Frame, Renderer};
+use text_editor::backend::screen::Screen;
+use text_editor::backend::{Clear, CursorShape, Style};
+
+#[test]
+fn only_changed_cells_are_printed() {
+    let screen = Screen::new(10, 3);
+    let mut backend = screen.clone();
+    let mut renderer = Renderer::new();
+
+    let mut frame = Frame::new((10, 3));
+    frame.print("hello\nworld", Style::Normal);
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.lines(), ["hello", "world", ""]);
+    let printed = screen.printed();
+
+    // the same frame again prints nothing
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.printed(), printed);
+
+    frame.goto(2, 2);
+    frame.print("a", Style::Match);
+    frame.shape = CursorShape::Bar;
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.printed(), printed + 1);
+    assert_eq!(screen.line(2), "warld");
+    assert_eq!(screen.cell(2, 2).style, Style::Match);
+    assert_eq!(screen.cursor(), (3, 2));
+    assert_eq!(screen.shape(), CursorShape::Bar);
+
+    frame.goto(1, 1);
+    frame.clear(Clear::CurrentLine);
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.lines(), ["", "warld", ""]);
+}
+
+#[test]
+fn wide_graphemes_are_printed_whole() {
+    let screen = Screen::new(6, 1);
+    let mut backend = screen.clone();
+    let mut renderer = Renderer::new();
+
+    let mut frame = Frame::new((6, 1));
+    frame.print("ab日c", Style::Normal);
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.line(1), "ab日c");
+
+    // overwriting the right half of the wide grapheme removes it
+    frame.goto(4, 1);
+    frame.print("x", Style::Normal);
+    assert_eq!(frame.line(1), "ab xc");
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.line(1), "ab xc");
+
+    frame.goto(2, 1);
+    frame.print("本", Style::Normal);
+    renderer.render(&frame, &mut backend);
+    assert_eq!(screen.line(1), "a本xc");
+}
@@ -0,0 +1,156 @@
+use text_editor::motion::Motions;
+use text_editor::normal::{parse, Action, Operator, Parsed, Parser};
+use text_editor::window::piece_table::PieceTable;
+
+fn operate(op: Operator, motion: Motions, count: Option<usize>) -> Parsed {
+    Parsed::Done(Action::Operate { op, motion, count })
+}
+
+#[test]
+fn parses_counts_operators_and_motions() {
+    assert_eq!(
+        parse("3dw"),
+        operate(Operator::Delete, Motions::WordForward, Some(3))
+    );
+    assert_eq!(
+        parse("2d3w"),
+        operate(Operator::Delete, Motions::WordForward, Some(6))
+    );
+    assert_eq!(
+        parse("d$"),
+        operate(Operator::Delete, Motions::LineEnd, None)
+    );
+    assert_eq!(
+        parse("c2j"),
+        operate(Operator::Change, Motions::Down, Some(2))
+    );
+    assert_eq!(parse("yy"), operate(Operator::Yank, Motions::Lines, None));
+    assert_eq!(
+        parse(">>"),
+        operate(Operator::ShiftRight, Motions::Lines, None)
+    );
+    assert_eq!(
+        parse("5x"),
+        operate(Operator::Delete, Motions::Right, Some(5))
+    );
+    assert_eq!(
+        parse("10j"),
+        Parsed::Done(Action::Move {
+            motion: Motions::Down,
+            count: Some(10)
+        })
+    );
+    assert_eq!(
+        parse("3gj"),
+        Parsed::Done(Action::Move {
+            motion: Motions::RowDown,
+            count: Some(3)
+        })
+    );
+    assert_eq!(
+        parse("0"),
+        Parsed::Done(Action::Move {
+            motion: Motions::LineStart,
+            count: None
+        })
+    );
+    assert_eq!(
+        parse("2u"),
+        Parsed::Done(Action::Key {
+            key: 'u',
+            count: Some(2)
+        })
+    );
+}
+
+#[test]
+fn keeps_pending_keys_until_complete() {
+    let mut parser = Parser::new();
+    assert_eq!(parser.feed('2'), Parsed::Pending);
+    assert_eq!(parser.feed('d'), Parsed::Pending);
+    assert_eq!(parser.pending(), "2d");
+    assert_eq!(parser.feed('q'), Parsed::Invalid);
+    assert_eq!(parser.pending(), "");
+    assert_eq!(parser.feed('d'), Parsed::Pending);
+    assert_eq!(
+        parser.feed('d'),
+        operate(Operator::Delete, Motions::Lines, None)
+    );
+}
+
+#[test]
+fn finds_motion_targets_in_piece_table() {
+    let mut data = PieceTable::new("foo.bar  baz\n\nqux\n");
+    data.insert("(", 0);
+    // "(foo.bar  baz\n\nqux\n"
+    let w = Motions::WordForward;
+    assert_eq!(w.target(&data, 0, 1), Some(1));
+    assert_eq!(w.target(&data, 1, 1), Some(4));
+    assert_eq!(w.target(&data, 1, 3), Some(10));
+    assert_eq!(w.target(&data, 10, 1), Some(14));
+    assert_eq!(w.target(&data, 14, 1), Some(15));
+    assert_eq!(Motions::LineEnd.target(&data, 2, 1), Some(12));
+    assert_eq!(Motions::LineEnd.target(&data, 2, 3), Some(17));
+    assert_eq!(Motions::Left.target(&data, 15, 1), None);
+    assert_eq!(Motions::Right.target(&data, 11, 5), Some(13));
+    assert_eq!(Motions::Down.target(&data, 3, 2), Some(15));
+    assert_eq!(Motions::Up.target(&data, 3, 1), None);
+}
+
+#[test]
+fn finds_word_targets_across_lines() {
+    let data = PieceTable::new("foo.bar baz\n\n  qux-1 end\n");
+    assert_eq!(Motions::WordBackward.target(&data, 8, 1), Some(4));
+    assert_eq!(Motions::WordBackward.target(&data, 8, 2), Some(3));
+    assert_eq!(Motions::WordBackward.target(&data, 15, 1), Some(12));
+    assert_eq!(Motions::BigWordBackward.target(&data, 8, 1), Some(0));
+    assert_eq!(Motions::WordEnd.target(&data, 0, 1), Some(2));
+    assert_eq!(Motions::WordEnd.target(&data, 8, 1), Some(10));
+    assert_eq!(Motions::WordEnd.target(&data, 10, 1), Some(17));
+    assert_eq!(Motions::BigWordEnd.target(&data, 14, 1), Some(19));
+    assert_eq!(Motions::BigWordForward.target(&data, 15, 1), Some(21));
+    assert_eq!(Motions::FirstNonBlank.target(&data, 20, 1), Some(15));
+}
+
+#[test]
+fn finds_line_paragraph_and_pair_targets() {
+    let data = PieceTable::new("a\nb\n\nc (x [y]\n z)\n\n  d\n");
+    assert_eq!(Motions::GotoLine.target(&data, 0, 7), Some(21));
+    assert_eq!(Motions::LastLine.target(&data, 0, 1), Some(21));
+    assert_eq!(Motions::GotoLine.target(&data, 21, 1), Some(0));
+    assert_eq!(Motions::ParagraphForward.target(&data, 0, 1), Some(4));
+    assert_eq!(Motions::ParagraphForward.target(&data, 0, 2), Some(18));
+    assert_eq!(Motions::ParagraphBackward.target(&data, 17, 1), Some(4));
+    assert_eq!(Motions::MatchPair.target(&data, 5, 1), Some(16));
+    assert_eq!(Motions::MatchPair.target(&data, 16, 1), Some(7));
+    assert_eq!(Motions::MatchPair.target(&data, 10, 1), Some(12));
+}
+
+#[test]
+fn finds_chars_and_repeats_finds() {
+    let data = PieceTable::new("a,b,c,d\ne,f\n");
+    let find = |ch, forward, till| Motions::FindChar { ch, forward, till };
+    assert_eq!(find(',', true, false).target(&data, 0, 2), Some(3));
+    assert_eq!(find(',', true, true).target(&data, 0, 1), None);
+    assert_eq!(find(',', true, true).target(&data, 0, 2), Some(2));
+    assert_eq!(find(',', false, false).target(&data, 6, 1), Some(5));
+    assert_eq!(find('e', true, false).target(&data, 0, 1), None);
+
+    let mut parser = Parser::new();
+    parser.feed('f');
+    parser.feed(',');
+    assert_eq!(
+        parser.feed(','),
+        Parsed::Done(Action::Move {
+            motion: find(',', false, false),
+            count: None
+        })
+    );
+    assert_eq!(
+        parse("3G"),
+        Parsed::Done(Action::Move {
+            motion: Motions::GotoLine,
+            count: Some(3)
+        })
+    );
+}
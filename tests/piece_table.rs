@@ -0,0 +1,175 @@
+use proptest::prelude::*;
+use proptest::sample::Index;
+use text_editor::window::piece_table::PieceTable;
+
+#[derive(Clone, Debug)]
+enum Op {
+    Insert(Index, String),
+    Delete(Index, Index),
+    /// deletes a range and puts its pieces back, like undo does
+    Restore(Index, Index),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        3 => (any::<Index>(), "[a-c\n é日]{1,6}").prop_map(|(i, s)| Op::Insert(i, s)),
+        2 => (any::<Index>(), any::<Index>()).prop_map(|(i, l)| Op::Delete(i, l)),
+        1 => (any::<Index>(), any::<Index>()).prop_map(|(i, l)| Op::Restore(i, l)),
+    ]
+}
+
+/// offsets where a char starts, including the end of the text
+fn boundaries(model: &str) -> Vec<usize> {
+    model
+        .char_indices()
+        .map(|(i, _)| i)
+        .chain(std::iter::once(model.len()))
+        .collect()
+}
+
+/// picks a range between 2 char boundaries, None for an empty text
+fn range(model: &str, at: &Index, len: &Index) -> Option<(usize, usize)> {
+    let bounds = boundaries(model);
+    if bounds.len() < 2 {
+        return None;
+    }
+
+    let from = at.index(bounds.len() - 1);
+    let to = from + 1 + len.index((bounds.len() - from - 1).min(8));
+    Some((bounds[from], bounds[to]))
+}
+
+fn lines(model: &str) -> Vec<&str> {
+    model.split_inclusive('\n').collect()
+}
+
+fn assert_same(table: &PieceTable, model: &str) {
+    assert_eq!(table.get_string(), model);
+    assert_eq!(table.len(), model.len());
+    assert_eq!(table.line_count(), lines(model).len().max(1));
+
+    let mut offset = 0;
+    for (i, line) in lines(model).iter().enumerate() {
+        let ln = i + 1;
+        assert_eq!(table.line_offset(ln), Some(offset));
+        assert_eq!(table.line_at(offset), ln);
+        assert_eq!(table.line_at(offset + line.len() - 1), ln);
+
+        let segment = table.get_lines(ln, ln);
+        let node = segment.front().expect("line should exist");
+        assert_eq!(node.value, *line);
+        assert_eq!(node.line_number, ln);
+        assert_eq!(node.offset, offset);
+
+        offset += line.len();
+    }
+
+    assert_eq!(table.get_lines(1, usize::MAX).construct_segment(), model);
+}
+
+proptest! {
+    #[test]
+    fn matches_string_model(original in "[a-c\n é]{0,20}", ops in prop::collection::vec(op(), 1..40)) {
+        let mut table = PieceTable::new(&original);
+        let mut model = original.clone();
+
+        for op in ops.iter() {
+            match op {
+                Op::Insert(at, items) => {
+                    let bounds = boundaries(&model);
+                    let offset = bounds[at.index(bounds.len())];
+                    table.insert(items, offset);
+                    model.insert_str(offset, items);
+                }
+                Op::Delete(at, len) => {
+                    if let Some((from, to)) = range(&model, at, len) {
+                        table.delete(from, to - from);
+                        model.replace_range(from..to, "");
+                    }
+                }
+                Op::Restore(at, len) => {
+                    if let Some((from, to)) = range(&model, at, len) {
+                        let pieces = table.pieces_at(from, to - from);
+                        table.delete(from, to - from);
+                        table.insert_pieces(from, &pieces);
+                    }
+                }
+            }
+
+            assert_same(&table, &model);
+        }
+    }
+}
+
+#[test]
+fn insert_into_add_piece() {
+    let mut table = PieceTable::new("hello\nworld\n");
+
+    table.insert("XY", 6);
+    table.insert("Z", 7);
+    table.insert("\n", 9);
+
+    assert_eq!(table.get_string(), "hello\nXZY\nworld\n");
+    assert_eq!(table.line_count(), 3);
+}
+
+#[test]
+fn delete_across_buffers() {
+    let mut table = PieceTable::new("hello\nworld\n");
+
+    table.insert("abc", 3);
+    table.delete(1, 9);
+
+    assert_eq!(table.get_string(), "horld\n");
+    assert_eq!(table.line_count(), 1);
+}
+
+#[test]
+fn empty_document_has_one_line() {
+    let table = PieceTable::new("");
+    let segment = table.get_lines(1, 10);
+
+    assert_eq!(table.line_count(), 1);
+    assert_eq!(segment.nodes.len(), 1);
+    assert_eq!(segment.front().unwrap().value, "");
+}
+
+#[test]
+fn typed_items_are_combined() {
+    let mut table = PieceTable::new("hello\n");
+
+    for (i, ch) in "abc".chars().enumerate() {
+        table.insert(&ch.to_string(), 2 + i);
+    }
+
+    assert_eq!(table.get_string(), "heabcllo\n");
+    assert_eq!(table.pieces_at(2, 3).len(), 1);
+}
+
+#[test]
+fn scrolls_over_lines_taking_many_rows() {
+    let table = PieceTable::new("a\nbbbb\nc\nd\ne\n");
+    // each item is a row of the screen
+    let rows = |line: &str| line.trim_end().len().max(1);
+    let lines = |segment: &text_editor::window::segment::Segment| {
+        segment
+            .nodes
+            .iter()
+            .map(|n| n.line_number)
+            .collect::<Vec<usize>>()
+    };
+
+    // line 2 is seen only partly on a screen of 3 rows
+    let mut segment = table.get_lines(1, 2);
+    table.next_line(&mut segment, 3, rows);
+    assert_eq!(lines(&segment), [2]);
+    table.next_line(&mut segment, 3, rows);
+    assert_eq!(lines(&segment), [3, 4, 5]);
+    table.next_line(&mut segment, 3, rows);
+    assert_eq!(lines(&segment), [3, 4, 5]);
+
+    table.prev_line(&mut segment, 3, rows);
+    assert_eq!(lines(&segment), [2]);
+    table.prev_line(&mut segment, 3, rows);
+    assert_eq!(lines(&segment), [1, 2]);
+}
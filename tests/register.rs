@@ -0,0 +1,132 @@
+use termion::event::Key;
+use text_editor::keys;
+use text_editor::normal::{Action, Parsed, Parser};
+use text_editor::register::{Register, Registers, Store};
+
+fn charwise(text: &str) -> Option<Register> {
+    Some(Register {
+        text: text.to_string(),
+        linewise: false,
+    })
+}
+
+fn linewise(text: &str) -> Option<Register> {
+    Some(Register {
+        text: text.to_string(),
+        linewise: true,
+    })
+}
+
+#[test]
+fn yanks_deletes_and_named_registers() {
+    let mut registers = Registers::new();
+
+    registers.store(None, "word", false, Store::Yank);
+    assert_eq!(registers.get(None, ""), charwise("word"));
+    assert_eq!(registers.get(Some('0'), ""), charwise("word"));
+
+    registers.store(None, "line one\n", true, Store::Delete);
+    registers.store(None, "line two\n", true, Store::Delete);
+    assert_eq!(registers.get(Some('"'), ""), linewise("line two\n"));
+    assert_eq!(registers.get(Some('1'), ""), linewise("line two\n"));
+    assert_eq!(registers.get(Some('2'), ""), linewise("line one\n"));
+    assert_eq!(registers.get(Some('0'), ""), charwise("word"));
+
+    // named registers don't touch the numbered ones
+    registers.store(Some('a'), "x", false, Store::Delete);
+    assert_eq!(registers.get(Some('1'), ""), linewise("line two\n"));
+    assert_eq!(registers.get(None, ""), charwise("x"));
+
+    registers.store(Some('_'), "gone", false, Store::Delete);
+    assert_eq!(registers.get(None, ""), charwise("x"));
+}
+
+#[test]
+fn uppercase_appends() {
+    let mut registers = Registers::new();
+
+    registers.store(Some('a'), "one", false, Store::Yank);
+    registers.store(Some('A'), " two", false, Store::Yank);
+    assert_eq!(registers.get(Some('a'), ""), charwise("one two"));
+
+    registers.store(Some('A'), "line\n", true, Store::Yank);
+    assert_eq!(registers.get(Some('A'), ""), linewise("one two\nline\n"));
+}
+
+#[test]
+fn file_name_register_is_read_only() {
+    let mut registers = Registers::new();
+    assert!(!Registers::is_writable(Some('%')));
+
+    registers.store(Some('%'), "text", false, Store::Yank);
+    assert_eq!(registers.get(Some('%'), "notes.txt"), charwise("notes.txt"));
+    assert_eq!(registers.get(Some('%'), ""), None);
+    assert_eq!(registers.list("notes.txt").len(), 1);
+}
+
+#[test]
+fn parser_takes_register_before_and_after_count() {
+    let mut parser = Parser::new();
+
+    for key in "3\"ay".chars() {
+        assert_eq!(parser.feed(key), Parsed::Pending);
+    }
+    assert_eq!(parser.pending(), "\"a3y");
+    assert!(matches!(
+        parser.feed('y'),
+        Parsed::Done(Action::Operate { .. })
+    ));
+    assert_eq!(parser.take_register(), Some('a'));
+
+    assert_eq!(parser.feed('"'), Parsed::Pending);
+    assert_eq!(parser.feed('!'), Parsed::Invalid);
+    assert_eq!(parser.take_register(), None);
+}
+
+#[test]
+fn recorded_keys_round_trip_through_text() {
+    let keys = [
+        Key::Char('0'),
+        Key::Char('i'),
+        Key::Char('\n'),
+        Key::Esc,
+        Key::Ctrl('r'),
+        Key::Backspace,
+        Key::Left,
+        Key::Delete,
+    ];
+    let text = keys::encode(&keys);
+    assert_eq!(text.chars().take(5).collect::<String>(), "0i\n\x1b\x12");
+    assert_eq!(keys::decode(&text), keys);
+
+    // function keys have no text
+    assert_eq!(keys::encode(&[Key::F(1), Key::Char('x')]), "x");
+
+    let mut registers = Registers::new();
+    registers.record('q', "dd");
+    registers.record('Q', "j");
+    assert_eq!(registers.get(Some('q'), ""), charwise("ddj"));
+    assert_eq!(registers.get(None, ""), None);
+}
+
+#[test]
+fn parser_names_registers_to_record_and_play() {
+    let mut parser = Parser::new();
+    let key = |key, count| Parsed::Done(Action::Key { key, count });
+
+    assert_eq!(parser.feed('q'), Parsed::Pending);
+    assert_eq!(parser.feed('a'), key('q', None));
+    assert_eq!(parser.take_register(), Some('a'));
+
+    parser.recording = true;
+    assert_eq!(parser.feed('q'), key('q', None));
+    assert_eq!(parser.take_register(), None);
+
+    for ch in "12@".chars() {
+        assert_eq!(parser.feed(ch), Parsed::Pending);
+    }
+    assert_eq!(parser.feed('@'), key('@', Some(12)));
+    assert_eq!(parser.take_register(), Some('@'));
+    assert_eq!(parser.feed('@'), Parsed::Pending);
+    assert_eq!(parser.feed('!'), Parsed::Invalid);
+}
@@ -0,0 +1,114 @@
+use text_editor::window::piece_table::PieceTable;
+use text_editor::window::search::{compile, find, find_in, substitutions, Direction, Pattern};
+
+fn pattern(p: &str) -> Pattern {
+    Pattern::new(p).unwrap()
+}
+
+/// "hello world\nhello there\n" split into several pieces, so matches cross piece boundaries
+fn table() -> PieceTable {
+    let mut table = PieceTable::new("hel world\nhello there\n");
+    table.insert("l", 2);
+    table.insert("o", 4);
+    table
+}
+
+#[test]
+fn finds_across_pieces() {
+    let table = table();
+    assert_eq!(table.get_string(), "hello world\nhello there\n");
+    assert_eq!(
+        find(&table, &pattern("llo w"), 0, Direction::Forward),
+        Some((2, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("hello"), 0, Direction::Forward),
+        Some((12, false))
+    );
+}
+
+#[test]
+fn wraps_around_the_document() {
+    let table = table();
+    assert_eq!(
+        find(&table, &pattern("hello"), 12, Direction::Forward),
+        Some((0, true))
+    );
+    assert_eq!(
+        find(&table, &pattern("hello"), 12, Direction::Backward),
+        Some((0, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("hello"), 0, Direction::Backward),
+        Some((12, true))
+    );
+    assert_eq!(find(&table, &pattern("bye"), 0, Direction::Forward), None);
+}
+
+#[test]
+fn finds_matches_within_range() {
+    let table = PieceTable::new("aaaa aa\n");
+    assert_eq!(
+        find_in(&table, &pattern("aa"), 0, 8),
+        vec![(0, 2), (2, 2), (5, 2)]
+    );
+    assert_eq!(find_in(&table, &pattern("aa"), 1, 6), vec![(1, 2)]);
+}
+
+#[test]
+fn finds_regex_matches_by_lines() {
+    let table = table();
+    assert!(matches!(pattern("h.llo"), Pattern::Regex(_)));
+    assert_eq!(
+        find(&table, &pattern("h.llo"), 0, Direction::Forward),
+        Some((12, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("^h"), 12, Direction::Forward),
+        Some((0, true))
+    );
+    assert_eq!(
+        find(&table, &pattern("d$"), 20, Direction::Backward),
+        Some((10, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("e$"), 5, Direction::Backward),
+        Some((22, true))
+    );
+    assert_eq!(
+        find_in(&table, &pattern("l+o"), 0, 24),
+        vec![(2, 3), (14, 3)]
+    );
+}
+
+#[test]
+fn expands_groups_in_substitutions() {
+    let table = table();
+    let regex = compile(r"(\w+) (\w+)", false).unwrap();
+    let res = substitutions(&table, &regex, r"\2 \1 & \&", (1, 2), false);
+
+    assert_eq!(res.len(), 2);
+    assert_eq!(res[0].text, "world hello hello world &");
+    assert_eq!((res[1].offset, res[1].len, res[1].line), (12, 11, 2));
+
+    let regex = compile("L", true).unwrap();
+    assert_eq!(substitutions(&table, &regex, "", (1, 1), false).len(), 1);
+    assert_eq!(substitutions(&table, &regex, "", (1, 1), true).len(), 3);
+}
+
+#[test]
+fn starts_after_a_multibyte_char_under_the_cursor() {
+    let table = PieceTable::new("é x\n日本 é\n");
+    assert_eq!(
+        find(&table, &pattern("x"), 0, Direction::Forward),
+        Some((3, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("é"), 5, Direction::Forward),
+        Some((12, false))
+    );
+    assert_eq!(
+        find(&table, &pattern("é"), 12, Direction::Forward),
+        Some((0, true))
+    );
+}
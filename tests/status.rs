@@ -0,0 +1,46 @@
+use text_editor::status::{self, Status};
+
+fn info() -> Status<'static> {
+    Status {
+        mode: "NORMAL",
+        file: "main.rs",
+        modified: true,
+        filetype: "rust",
+        line: 5,
+        col: 3,
+        lines: 20,
+        line_ending: "unix",
+        encoding: "utf-8",
+    }
+}
+
+#[test]
+fn items_are_expanded() {
+    let info = info();
+    assert_eq!(
+        status::render("%M %f %m %y %l:%c/%L %p%% %o %e %x", &info, 48),
+        "NORMAL main.rs [+] rust 5:3/20 25% unix utf-8 %x"
+    );
+
+    let empty = Status::default();
+    assert_eq!(status::render("%f%m", &empty, 9), "[No Name]");
+}
+
+#[test]
+fn right_part_is_aligned_and_kept_when_too_long() {
+    let info = info();
+    assert_eq!(status::render("%f%=%l", &info, 12), "main.rs    5");
+    assert_eq!(status::render("%f %y%=%l:%c", &info, 10), "main.rs5:3");
+    assert_eq!(status::render("%f%=%l:%c", &info, 2), "5:");
+}
+
+#[test]
+fn file_type_and_line_ending() {
+    assert_eq!(status::filetype("src/main.rs"), "rust");
+    assert_eq!(status::filetype("README.MD"), "markdown");
+    assert_eq!(status::filetype("Makefile"), "");
+
+    assert_eq!(status::line_ending("a\r\nb\r\n"), "dos");
+    assert_eq!(status::line_ending("a\nb\n"), "unix");
+    assert_eq!(status::line_ending(""), "unix");
+}
@@ -0,0 +1,129 @@
+use text_editor::motion::Motions;
+use text_editor::normal::{parse, parse_visual, Action, Operator, Parsed};
+use text_editor::text_object::TextObject;
+use text_editor::window::piece_table::PieceTable;
+
+/// text the object around the first occurrence of at takes
+fn taken(text: &str, at: &str, key: char, inner: bool) -> Option<String> {
+    let data = PieceTable::new(text);
+    let offset = text.find(at).unwrap();
+    let object = TextObject::from_key(key).unwrap();
+    let (from, to, _) = object.range(&data, offset, inner, 1)?;
+    Some(text[from..to].to_string())
+}
+
+#[test]
+fn parses_objects_after_operators_and_in_visual_mode() {
+    let object = TextObject::Pair('(', ')');
+    assert_eq!(
+        parse("di("),
+        Parsed::Done(Action::Operate {
+            op: Operator::Delete,
+            motion: Motions::Object {
+                object,
+                inner: true
+            },
+            count: None
+        })
+    );
+    assert_eq!(parse("ca"), Parsed::Pending);
+    assert_eq!(parse("diz"), Parsed::Invalid);
+    assert_eq!(
+        parse("i"),
+        Parsed::Done(Action::Key {
+            key: 'i',
+            count: None
+        })
+    );
+    assert_eq!(
+        parse_visual("2ab"),
+        Parsed::Done(Action::Move {
+            motion: Motions::Object {
+                object,
+                inner: false
+            },
+            count: Some(2)
+        })
+    );
+    assert_eq!(
+        parse_visual("d"),
+        Parsed::Done(Action::Key {
+            key: 'd',
+            count: None
+        })
+    );
+}
+
+#[test]
+fn words_and_sentences() {
+    let text = "one two, three\n";
+    assert_eq!(taken(text, "two", 'w', true).unwrap(), "two");
+    assert_eq!(taken(text, "two", 'w', false).unwrap(), " two");
+    assert_eq!(taken(text, "wo,", 'W', false).unwrap(), "two, ");
+    assert_eq!(taken(text, "one", 'w', false).unwrap(), "one ");
+    assert_eq!(taken(text, "three", 'w', false).unwrap(), " three");
+    assert_eq!(taken(text, " two", 'w', true).unwrap(), " ");
+
+    let text = "First one. Second one!  Third\nstill third.\n\nNext.\n";
+    assert_eq!(taken(text, "Second", 's', true).unwrap(), "Second one!");
+    assert_eq!(taken(text, "Second", 's', false).unwrap(), "Second one!  ");
+    assert_eq!(
+        taken(text, "still", 's', true).unwrap(),
+        "Third\nstill third."
+    );
+}
+
+#[test]
+fn paragraphs_are_whole_lines() {
+    let text = "a\nb\n\n\nc\n";
+    let data = PieceTable::new(text);
+    let object = TextObject::Paragraph;
+
+    assert_eq!(object.range(&data, 2, true, 1), Some((0, 4, true)));
+    assert_eq!(object.range(&data, 2, false, 1), Some((0, 6, true)));
+    // the last paragraph takes the empty lines before it
+    assert_eq!(object.range(&data, 6, false, 1), Some((4, 8, true)));
+}
+
+#[test]
+fn quotes_pair_from_the_line_start() {
+    let text = "say \"hi \\\" there\" and 'x'\n";
+    assert_eq!(taken(text, "hi", '"', true).unwrap(), "hi \\\" there");
+    assert_eq!(taken(text, "hi", '"', false).unwrap(), "\"hi \\\" there\" ");
+    // before the first quote the pair after the cursor is taken
+    assert_eq!(taken(text, "say", '"', true).unwrap(), "hi \\\" there");
+    assert_eq!(taken(text, "x", '\'', false).unwrap(), " 'x'");
+    assert_eq!(taken(text, "say", '`', true), None);
+}
+
+#[test]
+fn brackets_nest_and_span_lines() {
+    let text = "f(a, (b), c)\n";
+    assert_eq!(taken(text, "a,", '(', true).unwrap(), "a, (b), c");
+    assert_eq!(taken(text, "b)", 'b', false).unwrap(), "(b)");
+    assert_eq!(taken(text, "), c", ')', false).unwrap(), "(b)");
+    assert_eq!(taken(text, "f(", '[', true), None);
+
+    let data = PieceTable::new(text);
+    let object = TextObject::Pair('(', ')');
+    assert_eq!(object.range(&data, 6, false, 2), Some((1, 12, false)));
+
+    let text = "fn f() {\n    body;\n}\n";
+    assert_eq!(taken(text, "body", '{', true).unwrap(), "    body;\n");
+    assert_eq!(taken(text, "body", 'B', false).unwrap(), "{\n    body;\n}");
+}
+
+#[test]
+fn tags_skip_closed_elements() {
+    let text = "<div id=\"x\"><p>one</p><br/>two</div>\n";
+    assert_eq!(taken(text, "two", 't', true).unwrap(), "<p>one</p><br/>two");
+    assert_eq!(taken(text, "one", 't', true).unwrap(), "one");
+    assert_eq!(taken(text, "one", 't', false).unwrap(), "<p>one</p>");
+    assert_eq!(
+        taken(text, "/div", 't', false).unwrap(),
+        "<div id=\"x\"><p>one</p><br/>two</div>"
+    );
+
+    let text = "<a>\n  <a>in</a>\n</a>\n";
+    assert_eq!(taken(text, "\n</a>", 't', true).unwrap(), "\n  <a>in</a>\n");
+}
@@ -0,0 +1,65 @@
+use text_editor::window::wrap::Layout;
+
+fn layout(width: usize) -> Layout {
+    Layout {
+        wrap: true,
+        width,
+        tabstop: 4,
+        linebreak: false,
+        showbreak: String::new(),
+    }
+}
+
+#[test]
+fn long_lines_are_split_into_rows() {
+    let layout = layout(4);
+    assert_eq!(layout.rows("abcdefghij\n"), [0, 4, 8]);
+    assert_eq!(layout.rows("abcd\n"), [0]);
+    assert_eq!(layout.rows(""), [0]);
+
+    // a wide grapheme that doesn't fit goes to the next row, tabs reach the next tab stop
+    assert_eq!(layout.rows("abc日本"), [0, 3]);
+    assert_eq!(layout.rows("a\tbcd"), [0, 2]);
+
+    let nowrap = Layout {
+        wrap: false,
+        ..layout
+    };
+    assert_eq!(nowrap.rows("abcdefghij"), [0]);
+}
+
+#[test]
+fn linebreak_and_showbreak_change_where_rows_start() {
+    let mut layout = layout(8);
+    layout.linebreak = true;
+    assert_eq!(layout.rows("one two three"), [0, 8]);
+    assert_eq!(layout.rows("one twothree"), [0, 4]);
+    // a word longer than the row is still split
+    assert_eq!(layout.rows("abcdefghijk"), [0, 8]);
+
+    layout.linebreak = false;
+    layout.showbreak = ">>".to_string();
+    assert_eq!(layout.rows("abcdefghijklmnopq"), [0, 8, 14]);
+}
+
+#[test]
+fn columns_are_found_within_rows() {
+    let mut layout = layout(4);
+    layout.showbreak = "+".to_string();
+    let line = "abcdefgh\n";
+    let rows = layout.rows(line);
+    assert_eq!(rows, [0, 4, 7]);
+
+    assert_eq!(layout.row_of(&rows, 0), 0);
+    assert_eq!(layout.row_of(&rows, 5), 1);
+    assert_eq!(layout.row_of(&rows, 8), 2);
+
+    assert_eq!(layout.x_of(line, &rows, 2), 3);
+    assert_eq!(layout.x_of(line, &rows, 4), 2);
+    assert_eq!(layout.x_of(line, &rows, 8), 3);
+
+    assert_eq!(layout.idx_at(line, &rows, 1, 3), 5);
+    // showbreak and columns after the row end are at the nearest grapheme
+    assert_eq!(layout.idx_at(line, &rows, 1, 1), 4);
+    assert_eq!(layout.idx_at(line, &rows, 2, 4), 7);
+}